@@ -0,0 +1,277 @@
+use std::{error::Error, path::PathBuf};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use tracing::Level;
+
+use crate::{
+    config::SourcesConfig, dataset::format::RecordOrder, modlist::ModlistFormat, sources::registry,
+};
+
+/// Static site generator and tooling for JuMLi
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// Maximum level of log messages to print
+    #[arg(long, global = true, default_value_t = Level::INFO)]
+    pub log_level: Level,
+
+    /// Site configuration file; options given on the command line take precedence
+    #[arg(short, long, global = true)]
+    pub config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Build the site into an output directory
+    Build(BuildArgs),
+    /// Check the datasets for syntax errors and common mistakes
+    Validate(ValidateArgs),
+    /// Print the consolidated record for a package id or workshop id
+    Lookup(LookupArgs),
+    /// Compare the consolidated JuMLi datasets between two revisions
+    Diff(DiffArgs),
+    /// Check a RimSort clipboard export against the database
+    Check(CheckArgs),
+    /// Rewrite dataset files into the canonical layout
+    Fmt(FmtArgs),
+    /// Compare a CSV export of the Dubs community slow mods spreadsheet with its dataset
+    ImportDubs(ImportDubsArgs),
+    /// Print the JSON Schema for dataset files
+    Schema(SchemaArgs),
+    /// List the available record sources and their options
+    Sources,
+}
+
+#[derive(Args, Debug)]
+pub struct BuildArgs {
+    /// Directory to write the site to; must already exist and be empty unless building incrementally
+    #[arg(short, long)]
+    pub out_dir: PathBuf,
+
+    /// Directory containing static assets to copy into the site
+    #[arg(short = 'S', long)]
+    pub static_dir: Option<PathBuf>,
+
+    /// Base URL the site is served from, used for the sitemap
+    #[arg(long)]
+    pub base_url: Option<String>,
+
+    /// Produce byte-identical output for identical inputs, taking timestamps from
+    /// SOURCE_DATE_EPOCH or the most recent source commit
+    #[arg(long)]
+    pub reproducible: bool,
+
+    /// Reuse an existing output directory, only rewriting changed files and removing stale reports
+    /// and redirects
+    #[arg(long)]
+    pub incremental: bool,
+
+    #[command(flatten)]
+    pub sources: SourceArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct ValidateArgs {
+    /// Treat warnings as errors
+    #[arg(long)]
+    pub strict: bool,
+
+    #[command(flatten)]
+    pub data: DataArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct LookupArgs {
+    /// Package id or workshop id to look up
+    pub identifier: String,
+
+    #[command(flatten)]
+    pub sources: SourceArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// Local repository containing jumli_data
+    #[arg(long, default_value = ".")]
+    pub repository: PathBuf,
+
+    /// Revision to compare against
+    #[arg(long, default_value = "HEAD")]
+    pub base: String,
+
+    /// Revision to compare; the working copy is used if omitted
+    #[arg(long)]
+    pub head: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct CheckArgs {
+    /// File containing the modlist: a RimSort clipboard or XML export, the game's ModsConfig.xml, a
+    /// saved mod list (.rml), Rentry markup, a plain list of package ids and workshop URLs or a save
+    /// (.rws); read from stdin if omitted
+    pub input: Option<PathBuf>,
+
+    /// Format of the modlist; detected from its contents if omitted, saves are recognized by their
+    /// .rws extension
+    #[arg(long, value_enum)]
+    pub modlist_format: Option<ModlistFormat>,
+
+    /// Mods or workshop content folder. Without a modlist, the installed mods are checked, also
+    /// reporting missing dependencies and conflicts between them. With a modlist, it is used to find the workshop ids and names of the active mods
+    #[arg(long)]
+    pub mods_dir: Option<PathBuf>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    #[command(flatten)]
+    pub sources: SourceArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct FmtArgs {
+    /// Dataset files to format; defaults to all datasets in the current checkout
+    pub files: Vec<PathBuf>,
+
+    /// Report unformatted files instead of rewriting them
+    #[arg(long)]
+    pub check: bool,
+
+    /// Also sort records
+    #[arg(long, value_enum)]
+    pub sort: Option<RecordOrder>,
+}
+
+#[derive(Args, Debug)]
+pub struct ImportDubsArgs {
+    /// CSV export of the spreadsheet
+    pub input: PathBuf,
+
+    /// Dataset to compare the spreadsheet with
+    #[arg(long, default_value = "jumli_data/records/shitlist.ron")]
+    pub dataset: PathBuf,
+
+    /// Write the updated dataset to this file, which may be the compared dataset itself
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct SchemaArgs {
+    /// File to write the schema to; printed to stdout if omitted
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Text,
+    /// JSON for use in scripts
+    Json,
+}
+
+/// Selection of record sources to build the database from.
+#[derive(Args, Debug)]
+pub struct SourceArgs {
+    /// Record sources to ingest instead of those enabled in the config; may be given multiple
+    /// times. See the sources command for the available ones
+    #[arg(short, long = "source", value_name = "NAME")]
+    pub sources: Vec<String>,
+
+    /// Set an option of a source, e.g. jumli_data.repository=URL; may be given multiple times
+    #[arg(short = 'O', long = "source-option", value_name = "SOURCE.KEY=VALUE", value_parser = parse_source_option)]
+    pub options: Vec<SourceOptionArg>,
+
+    /// Use cached snapshots of remote sources instead of fetching them
+    #[arg(long)]
+    pub offline: bool,
+
+    /// Directory to cache snapshots of remote sources in
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub data: DataArgs,
+}
+
+/// A source option given on the command line.
+#[derive(Clone, Debug)]
+pub struct SourceOptionArg {
+    pub source: String,
+    pub key: String,
+    pub value: String,
+}
+
+fn parse_source_option(arg: &str) -> Result<SourceOptionArg, Box<dyn Error + Send + Sync>> {
+    let (name, value) = arg.split_once('=').ok_or("Expected SOURCE.KEY=VALUE")?;
+    let (source, key) = name.split_once('.').ok_or("Expected SOURCE.KEY=VALUE")?;
+    Ok(SourceOptionArg {
+        source: source.into(),
+        key: key.into(),
+        value: value.into(),
+    })
+}
+
+/// Location of the JuMLi datasets, shorthands for options of the jumli_data source.
+#[derive(Args, Debug)]
+pub struct DataArgs {
+    /// Root of a local jumli checkout to read datasets from instead of cloning GitHub
+    #[arg(long)]
+    pub data_dir: Option<PathBuf>,
+
+    /// Read datasets from this revision of the data directory instead of the working copy
+    #[arg(long)]
+    pub data_revision: Option<String>,
+}
+
+impl SourceArgs {
+    /// Override the source selection and options in `config` with the ones given.
+    pub fn apply_to(&self, config: &mut SourcesConfig) {
+        if !self.sources.is_empty() {
+            config.selected = Some(
+                self.sources
+                    .iter()
+                    .map(|x| registry::normalize_name(x))
+                    .collect(),
+            );
+        }
+        if self.offline {
+            config.offline = true;
+        }
+        if let Some(cache_dir) = &self.cache_dir {
+            config.cache_dir = Some(cache_dir.clone());
+        }
+        self.data.apply_to(config);
+        for option in &self.options {
+            config
+                .source_mut(&option.source)
+                .overrides
+                .insert(option.key.clone(), option.value.clone().into());
+        }
+    }
+}
+
+impl DataArgs {
+    /// Override the dataset location in `config` with the one given.
+    pub fn apply_to(&self, config: &mut SourcesConfig) {
+        let jumli_data = config.source_mut("jumli_data");
+        if let Some(data_dir) = &self.data_dir {
+            jumli_data.overrides.insert(
+                "data_dir".into(),
+                data_dir.to_string_lossy().into_owned().into(),
+            );
+            // A revision from the config refers to the configured directory
+            jumli_data.options.remove("data_revision");
+        }
+        if let Some(revision) = &self.data_revision {
+            jumli_data
+                .overrides
+                .insert("data_revision".into(), revision.clone().into());
+        }
+    }
+}
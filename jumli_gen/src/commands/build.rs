@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+use std::{env, error::Error, fmt::Display, path::Path, process::ExitCode};
+
+use chrono::{DateTime, TimeZone, Utc};
+use mapmysite::{ChangeFreq, Sitemap, SitemapUrl};
+use tracing::info;
+
+use crate::{
+    cli::BuildArgs,
+    commands::build_database,
+    config::SiteConfig,
+    dataset::dataset_schema,
+    output::OutputDir,
+    records::{Database, types::ModIdentifier},
+    render::{RenderHtml, diagnostics_data, frame_html, render_diagnostics},
+    sources::registry::SourceRegistry,
+};
+
+pub async fn run(args: BuildArgs, mut config: SiteConfig) -> Result<ExitCode, Box<dyn Error>> {
+    let out_path = args.out_dir;
+    if let Some(base_url) = args.base_url {
+        config.base_url = base_url;
+    }
+    args.sources.apply_to(&mut config.sources);
+    let base_url = config.base_url.trim_end_matches('/');
+    let paths = &config.paths;
+
+    if !out_path.is_dir() {
+        return Err(
+            format!("Output directory {out_path:?} does not exist or is not a directory.").into(),
+        );
+    }
+
+    let db = build_database(&config, &SourceRegistry::builtin()).await?;
+    let build_time = build_time(args.reproducible, &db)?;
+    info!("Using build time {}.", build_time.to_rfc3339());
+    let urls = SiteUrls {
+        base_url,
+        build_time,
+    };
+
+    let mut output = OutputDir::new(out_path, args.incremental);
+    let mut sitemap = Sitemap::new();
+    sitemap.add_url(urls.url(""));
+
+    if let Some(static_path) = args.static_dir {
+        info!("Copying static assets.");
+        copy_static(&mut sitemap, &urls, &static_path, &mut output)?;
+    }
+
+    info!("Rendering diagnostics.");
+    output.write(
+        &paths.diagnostics_report,
+        render_diagnostics(&db, &config, build_time),
+    )?;
+    sitemap.add_url(urls.url(&paths.diagnostics_report).priority(1.0));
+    output.write(
+        &paths.diagnostics_data,
+        serde_json::to_vec_pretty(&diagnostics_data(&db, build_time))?,
+    )?;
+    output.write(
+        &paths.dataset_schema,
+        serde_json::to_vec_pretty(&dataset_schema())?,
+    )?;
+    let mods_path = Path::new(&paths.mod_reports);
+    output.create_dir(mods_path)?;
+
+    info!("Saving index.");
+    output.write(
+        mods_path.join("index.json"),
+        serde_json::to_vec(
+            &db.indices
+                .iter()
+                .filter(|(_, idx)| db.records[**idx].notices.iter().any(|x| !x.historical))
+                .map(|(identifier, idx)| (identifier, &db.records[*idx].id))
+                .collect::<BTreeMap<_, _>>(),
+        )?,
+    )?;
+
+    info!("Rendering reports.");
+    let workshop_path = Path::new(&paths.workshop_redirects);
+    let package_path = Path::new(&paths.package_redirects);
+    output.create_dir(workshop_path)?;
+    output.create_dir(package_path)?;
+    for record in &db.records {
+        let record_id = &record.id;
+        output.write(
+            mods_path.join(format!("{record_id}.json")),
+            serde_json::to_vec(&record)?,
+        )?;
+
+        // Mods without notices still get a report if they are recommended as an alternative
+        if !record.notices.iter().any(|x| !x.historical) && record.recommended_instead_of.is_empty()
+        {
+            continue; // TODO: Display historical notices separately
+        }
+
+        output.write(
+            mods_path.join(format!("{record_id}.html")),
+            record.render_html(&config, &db),
+        )?;
+
+        'inner: for identifier in &record.identifiers {
+            match identifier {
+                ModIdentifier::PackageId(id) => {
+                    if !identifier.is_redirectable() {
+                        continue 'inner;
+                    }
+                    output.create_dir(package_path.join(id))?;
+                    output.write(
+                        package_path.join(format! {"{id}/index.html"}),
+                        frame_html(format!("/{}/{record_id}.html", paths.mod_reports)),
+                    )?;
+
+                    sitemap.add_url(
+                        urls.url(format!("{}/{id}", paths.package_redirects))
+                            .priority(0.5),
+                    );
+                }
+
+                ModIdentifier::WorkshopId(id) => {
+                    output.create_dir(workshop_path.join(id.to_string()))?;
+                    output.write(
+                        workshop_path.join(format!("{id}/index.html")),
+                        frame_html(format!("/{}/{record_id}.html", paths.mod_reports)),
+                    )?;
+
+                    sitemap.add_url(
+                        urls.url(format!("{}/{id}", paths.workshop_redirects))
+                            .priority(0.5),
+                    );
+                }
+            }
+        }
+    }
+    output.write("sitemap.xml", sitemap.to_string()?)?;
+
+    if args.incremental {
+        let removed = [
+            &paths.mod_reports,
+            &paths.workshop_redirects,
+            &paths.package_redirects,
+        ]
+        .into_iter()
+        .map(|subdir| output.remove_stale(subdir))
+        .sum::<Result<usize, _>>()?;
+        info!(
+            "Wrote {} files ({} unchanged), removed {removed} stale files.",
+            output.written_count(),
+            output.unchanged_count()
+        );
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Sitemap entries for pages of this build.
+struct SiteUrls<'a> {
+    base_url: &'a str,
+    build_time: DateTime<Utc>,
+}
+
+impl SiteUrls<'_> {
+    fn url(&self, path: impl Display) -> SitemapUrl {
+        SitemapUrl::from_base(self.base_url, path)
+            .change_frequency(ChangeFreq::Daily)
+            .last_modified(self.build_time)
+    }
+}
+
+/// Time used for timestamps in the output. `SOURCE_DATE_EPOCH` takes precedence; reproducible builds
+/// fall back to the most recent source commit instead of the current time.
+fn build_time(reproducible: bool, db: &Database) -> Result<DateTime<Utc>, Box<dyn Error>> {
+    if let Ok(epoch) = env::var("SOURCE_DATE_EPOCH") {
+        let seconds: i64 = epoch
+            .trim()
+            .parse()
+            .map_err(|e| format!("Invalid SOURCE_DATE_EPOCH {epoch:?}: {e}"))?;
+        return Utc
+            .timestamp_opt(seconds, 0)
+            .single()
+            .ok_or_else(|| format!("SOURCE_DATE_EPOCH {seconds} is out of range.").into());
+    }
+
+    if !reproducible {
+        return Ok(Utc::now());
+    }
+
+    db.latest_commit_time().ok_or_else(|| {
+        "Reproducible builds require SOURCE_DATE_EPOCH or a source with git information.".into()
+    })
+}
+
+fn copy_static(
+    sitemap: &mut Sitemap,
+    urls: &SiteUrls,
+    from: &Path,
+    output: &mut OutputDir,
+) -> Result<(), Box<dyn Error>> {
+    fn recurse(
+        sitemap: &mut Sitemap,
+        urls: &SiteUrls,
+        root: &Path,
+        from: &Path,
+        output: &mut OutputDir,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut entries = std::fs::read_dir(from)?.collect::<Result<Vec<_>, _>>()?;
+        // Keep sitemap order reproducible
+        entries.sort_by_key(|x| x.file_name());
+        for entry in entries {
+            let relative_path = entry.path().strip_prefix(root)?.to_path_buf();
+            if entry.file_type()?.is_dir() {
+                output.create_dir(&relative_path)?;
+                recurse(sitemap, urls, root, &entry.path(), output)?;
+                continue;
+            }
+
+            output.copy(entry.path(), &relative_path)?;
+            if entry.path().extension().and_then(|x| x.to_str()) == Some("html") {
+                sitemap.add_url(urls.url(relative_path.display()).priority(1.0));
+            }
+        }
+        Ok(())
+    }
+    recurse(sitemap, urls, from, from, output)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::sources::{Diagnostics, cache::CommitInfo};
+
+    #[test]
+    fn build_time_prefers_source_date_epoch() {
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.add_commit_info(&CommitInfo {
+            id: "0".repeat(40),
+            summary: None,
+            time: 1_700_000_000,
+            offset_minutes: 0,
+        });
+        let db = Database {
+            records: Vec::new(),
+            indices: HashMap::new(),
+            named_diagnostics: vec![("Source: Example".into(), diagnostics)],
+        };
+        let time = |seconds| Utc.timestamp_opt(seconds, 0).unwrap();
+
+        // SAFETY: No other test reads or writes SOURCE_DATE_EPOCH
+        unsafe { env::set_var("SOURCE_DATE_EPOCH", "1600000000") };
+        assert_eq!(build_time(true, &db).unwrap(), time(1_600_000_000));
+        assert_eq!(build_time(false, &db).unwrap(), time(1_600_000_000));
+        unsafe { env::set_var("SOURCE_DATE_EPOCH", "yesterday") };
+        assert!(build_time(true, &db).is_err());
+
+        unsafe { env::remove_var("SOURCE_DATE_EPOCH") };
+        assert_eq!(build_time(true, &db).unwrap(), time(1_700_000_000));
+        let empty = Database {
+            records: Vec::new(),
+            indices: HashMap::new(),
+            named_diagnostics: Vec::new(),
+        };
+        assert!(build_time(true, &empty).is_err());
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+    process::ExitCode,
+};
+
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::{
+    cli::{CheckArgs, OutputFormat},
+    commands::build_database,
+    config::SiteConfig,
+    modlist::{self, ModlistEntry, ModlistFormat, Position},
+    records::{
+        Database,
+        types::{ModIdentifier, Notice, NoticeRecord},
+    },
+    sources::{
+        mods_folder::{ModMetadata, ModsFolder},
+        registry::SourceRegistry,
+    },
+};
+
+#[derive(Serialize)]
+struct CheckResult<'a> {
+    name: &'a str,
+    identifiers: &'a [ModIdentifier],
+    /// Where the mod was found in the modlist, absent for installed mods
+    #[serde(skip_serializing_if = "Option::is_none")]
+    position: Option<Position>,
+    notices: Vec<&'a NoticeRecord>,
+    /// Id of the record the notices belong to
+    #[serde(skip)]
+    record_id: &'a str,
+}
+
+pub async fn run(args: CheckArgs, mut config: SiteConfig) -> Result<ExitCode, Box<dyn Error>> {
+    let installed = match &args.mods_dir {
+        Some(mods_dir) => read_mods_dir(mods_dir, &config.latest_rimworld_release)?,
+        None => Vec::new(),
+    };
+    // Without a modlist, the installed mods are checked
+    let check_installed = args.mods_dir.is_some() && args.input.is_none();
+
+    let entries = if check_installed {
+        installed.iter().map(ModlistEntry::from).collect()
+    } else {
+        let is_save = args.modlist_format == Some(ModlistFormat::Save)
+            || args
+                .input
+                .as_ref()
+                .and_then(|x| x.extension())
+                .is_some_and(|x| x.eq_ignore_ascii_case("rws"));
+        let modlist = match (&args.input, is_save) {
+            // Saves are large, only their start is read
+            (Some(path), true) => {
+                let file =
+                    File::open(path).map_err(|e| format!("Unable to read save {path:?}: {e}"))?;
+                modlist::parse_save(BufReader::new(file), &installed)?
+            }
+            (None, true) => modlist::parse_save(std::io::stdin().lock(), &installed)?,
+            (Some(path), false) => {
+                let input = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Unable to read modlist {path:?}: {e}"))?;
+                modlist::parse(&input, args.modlist_format, &installed)?
+            }
+            (None, false) => {
+                let mut input = String::new();
+                std::io::stdin().read_to_string(&mut input)?;
+                modlist::parse(&input, args.modlist_format, &installed)?
+            }
+        };
+        for warning in &modlist.warnings {
+            warn!("{warning}");
+        }
+        if modlist.entries.is_empty() {
+            return Err(format!(
+                "Modlist did not contain any mods, read as {}. Expected a RimSort export, ModsConfig.xml, saved mod list, Rentry markup or a list of package ids and workshop URLs.",
+                modlist.format
+            )
+            .into());
+        }
+        info!(
+            "Read {} written by RimWorld {}.",
+            modlist.format,
+            modlist
+                .game_version
+                .as_deref()
+                .unwrap_or("(unknown version)")
+        );
+        if let Some(version) = &modlist.game_version
+            && modlist::release_of(version) != config.latest_rimworld_release
+        {
+            warn!(
+                "The {} was written by RimWorld {version}, but mods are checked against RimWorld {}.",
+                modlist.format, config.latest_rimworld_release
+            );
+        }
+        if !modlist.known_expansions.is_empty() {
+            let expansions: Vec<_> = modlist
+                .known_expansions
+                .iter()
+                .map(|package_id| {
+                    let active = modlist.entries.iter().any(|entry| {
+                        entry.identifiers.iter().any(|x| {
+                            matches!(x, ModIdentifier::PackageId(id) if id.eq_ignore_ascii_case(package_id))
+                        })
+                    });
+                    if active {
+                        package_id.clone()
+                    } else {
+                        format!("{package_id} (inactive)")
+                    }
+                })
+                .collect();
+            info!("Expansions known to the game: {}.", expansions.join(", "));
+        }
+        let entries = modlist.entries;
+        info!("Parsed {} mods from modlist.", entries.len());
+        entries
+    };
+
+    args.sources.apply_to(&mut config.sources);
+    if let Some(mods_dir) = &args.mods_dir
+        && check_installed
+    {
+        // Installed mods are analyzed by the mods folder source, whichever other sources are used
+        let name = ModsFolder::DESCRIPTOR.name;
+        let source = config.sources.source_mut(name);
+        source.enabled = Some(true);
+        source.overrides.insert(
+            "path".into(),
+            mods_dir.to_string_lossy().into_owned().into(),
+        );
+        if let Some(selected) = &mut config.sources.selected {
+            selected.push(name.into());
+        }
+    }
+    let db = build_database(&config, &SourceRegistry::builtin()).await?;
+    let results = check(&entries, &db);
+
+    match args.format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&results)?),
+        OutputFormat::Text => {
+            if results.is_empty() {
+                println!("Nothing to report!");
+            }
+            for result in &results {
+                println!("{}", result.name);
+                for notice in &result.notices {
+                    println!("{}", format_notice(notice, result.record_id, &db));
+                }
+                println!();
+            }
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn read_mods_dir(mods_dir: &Path, release: &str) -> Result<Vec<ModMetadata>, Box<dyn Error>> {
+    let snapshot = ModsFolder::read_folder(mods_dir)?;
+    let (mods, errors) = ModsFolder::parse_snapshot(&snapshot, release);
+    for e in errors {
+        warn!("{e}");
+    }
+    if mods.is_empty() {
+        return Err(format!("No mods with an About/About.xml found in {mods_dir:?}.").into());
+    }
+    info!("Found {} mods in {mods_dir:?}.", mods.len());
+
+    Ok(mods)
+}
+
+fn check<'a>(entries: &'a [ModlistEntry], db: &'a Database) -> Vec<CheckResult<'a>> {
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let record = db.find_record(&entry.identifiers)?;
+            let notices: Vec<_> = record.notices.iter().filter(|x| !x.historical).collect();
+            if notices.is_empty() {
+                return None;
+            }
+
+            Some(CheckResult {
+                name: &entry.name,
+                identifiers: &entry.identifiers,
+                position: entry.position,
+                notices,
+                record_id: &record.id,
+            })
+        })
+        .collect()
+}
+
+/// Format a notice of the record with id `record_id`.
+fn format_notice(record: &NoticeRecord, record_id: &str, db: &Database) -> String {
+    let details = match &record.notice {
+        Notice::BadPerformance(details) | Notice::Unstable(details) => details.as_deref(),
+        Notice::UseAlternative(.., reason) => reason.as_deref(),
+        Notice::Bug(details) | Notice::Miscellaneous(details) => Some(details.as_str()),
+        Notice::OutOfDate => None,
+    };
+
+    let mut out = format!("  - {}", record.notice.title());
+    if let Notice::UseAlternative(name, workshop_id, _) = &record.notice {
+        out.push_str(&format!(": {name}"));
+        if let Some(id) = workshop_id {
+            out.push_str(&format!(
+                " (https://steamcommunity.com/sharedfiles/filedetails/?id={id})"
+            ));
+        }
+    }
+    out.push_str(&format!(" ({}", record.source));
+    if let Some(date) = record.date {
+        out.push_str(&format!(", {date}"));
+    }
+    for corroboration in &record.corroborations {
+        out.push_str(&format!("; {}", corroboration.source));
+        if let Some(date) = corroboration.date {
+            out.push_str(&format!(", {date}"));
+        }
+    }
+    out.push(')');
+    if let Some(details) = details {
+        out.push_str(&format!("\n    {details}"));
+    }
+    if let (Notice::UseAlternative(name, ..), Some(chain)) =
+        (&record.notice, &record.alternative_chain)
+    {
+        let path: Vec<&str> = std::iter::once(name.as_str())
+            .chain(chain.steps.iter().map(|x| x.name.as_str()))
+            .collect();
+        if chain.cycle {
+            out.push_str(&format!(
+                "\n    The recommended alternatives lead in a circle: {}",
+                path.join(" -> ")
+            ));
+        } else if let Some(last) = chain.steps.last() {
+            out.push_str(&format!(
+                "\n    This alternative is itself superseded: {}",
+                path.join(" -> ")
+            ));
+            let url = last.workshop_id.map_or(String::new(), |id| {
+                format!(" (https://steamcommunity.com/sharedfiles/filedetails/?id={id})")
+            });
+            out.push_str(&format!("\n    Final recommendation: {}{url}", last.name));
+        }
+    }
+    // If the alternative is another upload of the same mod, its notices are listed above already
+    if let Some(alternative) = db
+        .alternative_record(&record.notice)
+        .filter(|x| x.id != record_id)
+    {
+        let concerns: Vec<_> = alternative
+            .notices
+            .iter()
+            .filter(|x| x.is_concern())
+            .map(|x| format!("{} ({})", x.notice.title(), x.source))
+            .collect();
+        if !concerns.is_empty() {
+            out.push_str(&format!(
+                "\n    The alternative has notices of its own: {}",
+                concerns.join(", ")
+            ));
+        }
+    }
+    if let Some(context_url) = &record.context_url {
+        out.push_str(&format!("\n    Context: {context_url}"));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::records::types::{Certainty, ModRecord, Source};
+
+    fn notice(notice: Notice) -> NoticeRecord {
+        NoticeRecord {
+            date: NaiveDate::from_ymd_opt(2025, 1, 1),
+            notice,
+            certainty: Certainty::High,
+            source: Source::JumliDataset("Example".into()),
+            context_url: None,
+            historical: false,
+            alternative_chain: None,
+            corroborations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn formats_notices() {
+        let db = Database {
+            records: Vec::new(),
+            indices: HashMap::new(),
+            named_diagnostics: Vec::new(),
+        };
+        assert_eq!(
+            format_notice(&notice(Notice::OutOfDate), "author.mod", &db),
+            "  - Out Of Date (JuMLi Dataset: Example, 2025-01-01)"
+        );
+        assert_eq!(
+            format_notice(
+                &notice(Notice::Unstable(Some("Crashes on load".into()))),
+                "author.mod",
+                &db
+            ),
+            "  - Unstable (JuMLi Dataset: Example, 2025-01-01)\n    Crashes on load"
+        );
+        assert_eq!(
+            format_notice(
+                &notice(Notice::UseAlternative(
+                    "Other".into(),
+                    Some(1000000002),
+                    Some("Updated".into())
+                )),
+                "author.mod",
+                &db
+            ),
+            "  - Better Alternative Available: Other (https://steamcommunity.com/sharedfiles/filedetails/?id=1000000002) (JuMLi Dataset: Example, 2025-01-01)\n    Updated"
+        );
+    }
+
+    #[test]
+    fn summarizes_notices_of_other_alternatives_only() {
+        let alternative = notice(Notice::UseAlternative(
+            "Other".into(),
+            Some(1000000002),
+            None,
+        ));
+        let db = Database {
+            records: vec![ModRecord {
+                id: "1000000002".into(),
+                notices: vec![notice(Notice::Unstable(None))],
+                identifiers: vec![ModIdentifier::WorkshopId(1000000002)],
+                recommended_instead_of: Vec::new(),
+            }],
+            indices: HashMap::from([("1000000002".into(), 0)]),
+            named_diagnostics: Vec::new(),
+        };
+        assert!(format_notice(&alternative, "author.mod", &db).ends_with(
+            "\n    The alternative has notices of its own: Unstable (JuMLi Dataset: Example)"
+        ));
+        assert!(!format_notice(&alternative, "1000000002", &db).contains("notices of its own"));
+    }
+}
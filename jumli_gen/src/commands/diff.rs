@@ -0,0 +1,96 @@
+use std::{error::Error, process::ExitCode};
+
+use tracing::info;
+
+use crate::{
+    cli::DiffArgs,
+    records::{Database, DatabaseBuilder, SourcePolicy, types::ModRecord},
+    sources::jumli_data::{DataLocation, JumliData},
+};
+
+pub async fn run(args: DiffArgs) -> Result<ExitCode, Box<dyn Error>> {
+    let base = load(DataLocation::GitRevision {
+        repository: args.repository.clone(),
+        revision: args.base.clone(),
+    })
+    .await?;
+    let head = load(match args.head {
+        Some(revision) => DataLocation::GitRevision {
+            repository: args.repository,
+            revision,
+        },
+        None => DataLocation::Directory(args.repository),
+    })
+    .await?;
+
+    let (mut added, mut changed, mut removed) = (0, 0, 0);
+    // Records without notices only exist because other mods recommend them as alternatives
+    for record in head.records.iter().filter(|x| !x.notices.is_empty()) {
+        match find_counterpart(record, &base) {
+            None => {
+                added += 1;
+                println!("+ {}", describe(record));
+            }
+            Some(old) if !same_content(old, record)? => {
+                changed += 1;
+                println!(
+                    "~ {} ({} -> {} notices)",
+                    describe(record),
+                    old.notices.len(),
+                    record.notices.len()
+                );
+            }
+            Some(_) => {}
+        }
+    }
+
+    for record in base.records.iter().filter(|x| !x.notices.is_empty()) {
+        if find_counterpart(record, &head).is_none() {
+            removed += 1;
+            println!("- {}", describe(record));
+        }
+    }
+
+    info!("{added} added, {changed} changed, {removed} removed.");
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn load(location: DataLocation) -> Result<Database, Box<dyn Error>> {
+    let mut builder = DatabaseBuilder::new();
+    builder.add_source(
+        Box::new(JumliData::with_location(location)),
+        SourcePolicy {
+            required: true,
+            ..Default::default()
+        },
+    );
+    builder.ingest_all().await?;
+    Ok(builder.finalize().await)
+}
+
+fn find_counterpart<'a>(record: &ModRecord, db: &'a Database) -> Option<&'a ModRecord> {
+    record
+        .identifiers
+        .iter()
+        .find_map(|identifier| db.indices.get(&identifier.to_string()))
+        .map(|idx| &db.records[*idx])
+}
+
+fn same_content(a: &ModRecord, b: &ModRecord) -> Result<bool, Box<dyn Error>> {
+    let mut a_identifiers: Vec<String> = a.identifiers.iter().map(|x| x.to_string()).collect();
+    let mut b_identifiers: Vec<String> = b.identifiers.iter().map(|x| x.to_string()).collect();
+    a_identifiers.sort();
+    b_identifiers.sort();
+
+    Ok(a_identifiers == b_identifiers
+        && serde_json::to_value(&a.notices)? == serde_json::to_value(&b.notices)?)
+}
+
+fn describe(record: &ModRecord) -> String {
+    record
+        .identifiers
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,66 @@
+use std::{error::Error, path::PathBuf, process::ExitCode};
+
+use tracing::{error, info, warn};
+
+use crate::{
+    cli::FmtArgs,
+    dataset::{DatasetFormat, RawDataset, format::format},
+    sources::jumli_data::RECORDS_SUBDIR,
+};
+
+pub fn run(args: FmtArgs) -> Result<ExitCode, Box<dyn Error>> {
+    let paths = if args.files.is_empty() {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(RECORDS_SUBDIR)
+            .map_err(|e| format!("Unable to read records directory {RECORDS_SUBDIR:?}: {e}"))?
+            .filter_map(|entry| entry.ok().map(|x| x.path()))
+            .filter(|path| path.extension().is_some_and(|x| x == "ron"))
+            .collect();
+        paths.sort();
+        paths
+    } else {
+        args.files
+    };
+
+    let mut unformatted = 0;
+    for path in paths {
+        if DatasetFormat::from_path(&path) != Some(DatasetFormat::Ron) {
+            return Err(format!(
+                "{path:?} is not a RON dataset; only RON datasets can be formatted"
+            )
+            .into());
+        }
+        let raw = RawDataset {
+            name: path.display().to_string(),
+            format: DatasetFormat::Ron,
+            contents: std::fs::read_to_string(&path)
+                .map_err(|e| format!("Unable to read dataset {path:?}: {e}"))?,
+        };
+
+        let formatted = format(&raw, args.sort)?;
+        if formatted.dropped_comments > 0 {
+            warn!(
+                "{}: {} comments not preceding a record will be dropped.",
+                raw.name, formatted.dropped_comments
+            );
+        }
+
+        if formatted.contents == raw.contents {
+            continue;
+        }
+
+        if args.check {
+            println!("{} is not formatted", raw.name);
+            unformatted += 1;
+        } else {
+            std::fs::write(&path, formatted.contents)?;
+            info!("Formatted {}.", raw.name);
+        }
+    }
+
+    if unformatted > 0 {
+        error!("{unformatted} datasets are not formatted. Run `jumli_gen fmt` to fix them.");
+        return Ok(ExitCode::FAILURE);
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
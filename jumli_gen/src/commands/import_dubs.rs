@@ -0,0 +1,225 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    error::Error,
+    process::ExitCode,
+};
+
+use chrono::Utc;
+use tracing::{info, warn};
+
+use crate::{
+    cli::ImportDubsArgs,
+    dataset::{
+        DatasetFileRecord, DatasetFormat, LocalNotice, RawDataset,
+        dubs::{self, DateOrder, SheetMod},
+        format::{inline, write},
+        scan::{RecordLocation, scan},
+    },
+    records::types::{ModIdentifier, Notice},
+};
+
+pub fn run(args: ImportDubsArgs) -> Result<ExitCode, Box<dyn Error>> {
+    let csv = std::fs::read_to_string(&args.input)
+        .map_err(|e| format!("Unable to read spreadsheet {:?}: {e}", args.input))?;
+    let sheet = dubs::parse(&csv, Utc::now().date_naive())?;
+    for warning in &sheet.warnings {
+        warn!("{warning}");
+    }
+    info!(
+        "Read {} mods from the spreadsheet, ambiguous dates were read {}.",
+        sheet.mods.len(),
+        match sheet.date_order {
+            DateOrder::DayFirst => "day first",
+            DateOrder::MonthFirst => "month first",
+        }
+    );
+
+    if DatasetFormat::from_path(&args.dataset) != Some(DatasetFormat::Ron) {
+        return Err(format!("{:?} is not a RON dataset", args.dataset).into());
+    }
+    let raw = RawDataset {
+        name: args.dataset.display().to_string(),
+        format: DatasetFormat::Ron,
+        contents: std::fs::read_to_string(&args.dataset)
+            .map_err(|e| format!("Unable to read dataset {:?}: {e}", args.dataset))?,
+    };
+    let mut dataset = raw.parse().map_err(|e| format!("{}:{e}", raw.name))?;
+    let mut layout = scan(&raw.contents);
+    if layout.records.len() != dataset.records.len() {
+        return Err(format!(
+            "{}: Found {} records while scanning, but parsed {}",
+            raw.name,
+            layout.records.len(),
+            dataset.records.len()
+        )
+        .into());
+    }
+
+    let mut imported: BTreeMap<u64, &SheetMod> = BTreeMap::new();
+    for sheet_mod in &sheet.mods {
+        if let Some(first) = imported.get(&sheet_mod.workshop_id) {
+            warn!(
+                "Row {}: {} is listed again, keeping row {}",
+                sheet_mod.row, sheet_mod.name, first.row
+            );
+            continue;
+        }
+        imported.insert(sheet_mod.workshop_id, sheet_mod);
+    }
+
+    let (mut added, mut changed, mut removed, mut unchanged) = (0, 0, 0, 0);
+    let mut seen = HashSet::new();
+    for (record, location) in dataset.records.iter_mut().zip(&mut layout.records) {
+        let sheet_mod = record.identifiers.iter().find_map(|x| match x {
+            ModIdentifier::WorkshopId(id) => imported.get(id),
+            ModIdentifier::PackageId(_) => None,
+        });
+        seen.extend(sheet_mod.map(|x| x.workshop_id));
+        let label = location
+            .comments
+            .first()
+            .cloned()
+            .unwrap_or_else(|| describe(&record.identifiers));
+
+        // Other notices were added by hand and are kept as they are
+        let current = record.notices.iter().position(is_imported);
+        match (current, sheet_mod.and_then(|x| x.notice.as_ref().ok())) {
+            (None, None) => {}
+            (None, Some(new)) => {
+                added += 1;
+                println!("+ {label}");
+                print_notice(new)?;
+                record.notices.push(new.clone());
+            }
+            (Some(idx), None) => {
+                removed += 1;
+                let reason = match sheet_mod {
+                    Some(sheet_mod) => sheet_mod.notice.as_ref().err().cloned(),
+                    None => Some("no longer listed".into()),
+                };
+                println!("- {label} ({})", reason.unwrap_or_default());
+                record.notices.remove(idx);
+                // Comments of the following notices move along with them
+                location.notice_comments = std::mem::take(&mut location.notice_comments)
+                    .into_iter()
+                    .filter(|((notice, _), _)| *notice != idx)
+                    .map(|((notice, field), comment)| {
+                        ((notice - usize::from(notice > idx), field), comment)
+                    })
+                    .collect();
+            }
+            (Some(idx), Some(new)) => {
+                let old = &mut record.notices[idx];
+                let changes = changes(old, new)?;
+                if changes.is_empty() {
+                    unchanged += 1;
+                    continue;
+                }
+
+                changed += 1;
+                println!("~ {label}");
+                for change in changes {
+                    println!("    {change}");
+                }
+                // Context links are not part of the spreadsheet
+                old.date = new.date;
+                old.notice = new.notice.clone();
+                old.certainty = new.certainty.clone();
+            }
+        }
+    }
+
+    let mut records: Vec<(RecordLocation, DatasetFileRecord)> = layout
+        .records
+        .into_iter()
+        .zip(dataset.records)
+        .filter(|(_, record)| !record.notices.is_empty())
+        .collect();
+    for sheet_mod in imported.values() {
+        let Ok(notice) = &sheet_mod.notice else {
+            continue;
+        };
+        if seen.contains(&sheet_mod.workshop_id) {
+            continue;
+        }
+
+        added += 1;
+        println!("+ {}", sheet_mod.name);
+        print_notice(notice)?;
+        records.push((
+            RecordLocation {
+                comments: vec![sheet_mod.name.clone()],
+                ..Default::default()
+            },
+            DatasetFileRecord {
+                identifiers: vec![ModIdentifier::WorkshopId(sheet_mod.workshop_id)],
+                notices: vec![notice.clone()],
+            },
+        ));
+    }
+
+    info!("{added} added, {changed} changed, {removed} removed, {unchanged} unchanged.");
+
+    if let Some(output) = args.output {
+        let records: Vec<_> = records
+            .iter()
+            .map(|(location, record)| (location, record))
+            .collect();
+        let (contents, _) = write(
+            &dataset.name,
+            &dataset.description,
+            &layout.header_comments,
+            &records,
+            &layout.trailing_comments,
+        )?;
+        std::fs::write(&output, contents)
+            .map_err(|e| format!("Unable to write dataset {output:?}: {e}"))?;
+        info!("Wrote the updated dataset to {output:?}.");
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Whether a notice is one the spreadsheet provides.
+fn is_imported(notice: &LocalNotice) -> bool {
+    !notice.historical
+        && matches!(
+            notice.notice,
+            Notice::BadPerformance(_) | Notice::Unstable(_)
+        )
+}
+
+fn print_notice(notice: &LocalNotice) -> Result<(), Box<dyn Error>> {
+    if let Some(date) = notice.date {
+        println!("    date: {date}");
+    }
+    println!("    notice: {}", inline(&notice.notice)?);
+    println!("    certainty: {}", inline(&notice.certainty)?);
+    Ok(())
+}
+
+/// Describe how the fields of two notices differ.
+fn changes(old: &LocalNotice, new: &LocalNotice) -> Result<Vec<String>, Box<dyn Error>> {
+    let date = |x: &LocalNotice| x.date.map_or("none".into(), |x| x.to_string());
+    let mut changes = Vec::new();
+    if old.date != new.date {
+        changes.push(format!("date: {} -> {}", date(old), date(new)));
+    }
+    let (old_notice, new_notice) = (inline(&old.notice)?, inline(&new.notice)?);
+    if old_notice != new_notice {
+        changes.push(format!("notice: {old_notice}\n         -> {new_notice}"));
+    }
+    let (old_certainty, new_certainty) = (inline(&old.certainty)?, inline(&new.certainty)?);
+    if old_certainty != new_certainty {
+        changes.push(format!("certainty: {old_certainty} -> {new_certainty}"));
+    }
+    Ok(changes)
+}
+
+fn describe(identifiers: &[ModIdentifier]) -> String {
+    identifiers
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,21 @@
+use std::{error::Error, process::ExitCode};
+
+use tracing::warn;
+
+use crate::{
+    cli::LookupArgs, commands::build_database, config::SiteConfig,
+    sources::registry::SourceRegistry,
+};
+
+pub async fn run(args: LookupArgs, mut config: SiteConfig) -> Result<ExitCode, Box<dyn Error>> {
+    args.sources.apply_to(&mut config.sources);
+    let db = build_database(&config, &SourceRegistry::builtin()).await?;
+
+    let Some(idx) = db.indices.get(args.identifier.trim()) else {
+        warn!("No record found for {:?}.", args.identifier);
+        return Ok(ExitCode::FAILURE);
+    };
+
+    println!("{}", serde_json::to_string_pretty(&db.records[*idx])?);
+    Ok(ExitCode::SUCCESS)
+}
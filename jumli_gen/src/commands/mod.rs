@@ -0,0 +1,37 @@
+use std::error::Error;
+
+use crate::{
+    config::SiteConfig,
+    records::{Database, DatabaseBuilder},
+    sources::registry::SourceRegistry,
+};
+
+pub mod build;
+pub mod check;
+pub mod diff;
+pub mod fmt;
+pub mod import_dubs;
+pub mod lookup;
+pub mod schema;
+pub mod sources;
+pub mod validate;
+
+/// Ingest all enabled sources of `registry` and consolidate them into a database.
+pub async fn build_database(
+    site_config: &SiteConfig,
+    registry: &SourceRegistry,
+) -> Result<Database, Box<dyn Error>> {
+    let config = &site_config.sources;
+    config.validate(registry)?;
+
+    let mut builder = DatabaseBuilder::with_cache(config.snapshot_cache());
+    for descriptor in registry.iter().filter(|x| config.is_enabled(x)) {
+        let source = descriptor
+            .create(site_config, &config.options(descriptor.name))
+            .map_err(|e| format!("Unable to set up source {}: {e}", descriptor.name))?;
+        builder.add_source(source, config.policy(descriptor));
+    }
+    builder.ingest_all().await?;
+
+    Ok(builder.finalize().await)
+}
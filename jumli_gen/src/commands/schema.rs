@@ -0,0 +1,15 @@
+use std::{error::Error, process::ExitCode};
+
+use crate::{cli::SchemaArgs, dataset::dataset_schema};
+
+pub fn run(args: SchemaArgs) -> Result<ExitCode, Box<dyn Error>> {
+    let schema = serde_json::to_string_pretty(&dataset_schema())?;
+
+    match args.output {
+        Some(path) => std::fs::write(&path, schema + "\n")
+            .map_err(|e| format!("Unable to write schema to {path:?}: {e}"))?,
+        None => println!("{schema}"),
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,30 @@
+use std::{error::Error, process::ExitCode};
+
+use crate::sources::registry::SourceRegistry;
+
+pub fn run() -> Result<ExitCode, Box<dyn Error>> {
+    for descriptor in SourceRegistry::builtin().iter() {
+        let mut flags = Vec::new();
+        if !descriptor.enabled_by_default {
+            flags.push("disabled by default");
+        }
+        if descriptor.required_by_default {
+            flags.push("required");
+        }
+        let flags = match flags.is_empty() {
+            true => String::new(),
+            false => format!(" ({})", flags.join(", ")),
+        };
+
+        println!("{}{flags}: {}", descriptor.name, descriptor.description);
+        for option in descriptor.options {
+            let default = option
+                .default
+                .map(|x| format!(" [default: {x}]"))
+                .unwrap_or_default();
+            println!("    {}: {}{default}", option.name, option.description);
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,37 @@
+use std::{error::Error, process::ExitCode};
+
+use chrono::Utc;
+use tracing::{error, info};
+
+use crate::{
+    cli::ValidateArgs,
+    config::SiteConfig,
+    dataset::lint::{Severity, lint},
+    sources::jumli_data::JumliData,
+};
+
+pub async fn run(args: ValidateArgs, mut config: SiteConfig) -> Result<ExitCode, Box<dyn Error>> {
+    args.data.apply_to(&mut config.sources);
+    let source = JumliData::from_options(&config.sources.options(JumliData::DESCRIPTOR.name))?;
+    let datasets = source.read_datasets().await?;
+    info!("Validating {} datasets.", datasets.len());
+
+    let messages = lint(&datasets, Utc::now().date_naive());
+    for message in &messages {
+        println!("{message}");
+    }
+
+    let errors = messages
+        .iter()
+        .filter(|x| x.severity == Severity::Error)
+        .count();
+    let warnings = messages.len() - errors;
+
+    if errors > 0 || (args.strict && warnings > 0) {
+        error!("Datasets failed to validate: {errors} errors, {warnings} warnings.");
+        Ok(ExitCode::FAILURE)
+    } else {
+        info!("Datasets are valid: {warnings} warnings.");
+        Ok(ExitCode::SUCCESS)
+    }
+}
@@ -0,0 +1,197 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    records::SourcePolicy,
+    sources::{
+        cache::SnapshotCache,
+        registry::{self, SourceDescriptor, SourceOptions, SourceRegistry},
+    },
+};
+
+/// Site configuration, read from a TOML file. Omitted values fall back to the defaults used for
+/// the public site.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct SiteConfig {
+    /// Base URL the site is served from, used for the sitemap
+    pub base_url: String,
+    /// Mods whose supported versions do not include this release are considered out of date
+    pub latest_rimworld_release: String,
+    pub paths: PathsConfig,
+    pub sources: SourcesConfig,
+}
+
+impl Default for SiteConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://jumli.sysrqmagician.dev".into(),
+            latest_rimworld_release: "1.6".into(),
+            paths: PathsConfig::default(),
+            sources: SourcesConfig::default(),
+        }
+    }
+}
+
+impl SiteConfig {
+    /// Read the configuration from `path`, or use the defaults if no path is given. Relative paths
+    /// in the file are resolved against its directory.
+    pub fn load(path: Option<&Path>) -> Result<Self, Box<dyn Error>> {
+        let Some(path) = path else {
+            return Ok(Self::default());
+        };
+
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read config {path:?}: {e}"))?;
+        let mut config: Self = toml::from_str(&contents)
+            .map_err(|e| format!("Unable to parse config {path:?}: {e}"))?;
+
+        if let Some(config_dir) = path.parent() {
+            if let Some(cache_dir) = &mut config.sources.cache_dir {
+                *cache_dir = config_dir.join(&*cache_dir);
+            }
+            config.sources.config_dir = Some(config_dir.to_path_buf());
+        }
+
+        Ok(config)
+    }
+}
+
+/// Locations of generated pages relative to the output directory. The list checking pages in
+/// jumli_static expect the defaults.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathsConfig {
+    /// Directory containing the HTML and JSON mod reports and the identifier index
+    pub mod_reports: String,
+    /// Directory containing redirects from workshop ids to mod reports
+    pub workshop_redirects: String,
+    /// Directory containing redirects from package ids to mod reports
+    pub package_redirects: String,
+    pub diagnostics_report: String,
+    /// Diagnostics as JSON, including the problems found in each source's input
+    pub diagnostics_data: String,
+    pub dataset_schema: String,
+}
+
+impl Default for PathsConfig {
+    fn default() -> Self {
+        Self {
+            mod_reports: "mods".into(),
+            workshop_redirects: "workshop".into(),
+            package_redirects: "package".into(),
+            diagnostics_report: "diagnostics.html".into(),
+            diagnostics_data: "diagnostics.json".into(),
+            dataset_schema: "dataset.schema.json".into(),
+        }
+    }
+}
+
+/// Options for all sources; sources are configured in tables named after them, see
+/// `SourceRegistry` for the available ones.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SourcesConfig {
+    /// Directory storing the last good snapshot of each remote source; see
+    /// `SnapshotCache::default_dir` for the default
+    pub cache_dir: Option<PathBuf>,
+    /// Use cached snapshots instead of fetching remote sources
+    pub offline: bool,
+    /// Sources to ingest instead of those enabled by the config, set from the command line
+    #[serde(skip)]
+    pub selected: Option<Vec<String>>,
+    /// Directory of the config file, which relative paths in source options are resolved against
+    #[serde(skip)]
+    pub config_dir: Option<PathBuf>,
+    #[serde(flatten)]
+    pub sources: BTreeMap<String, SourceConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SourceConfig {
+    /// Whether the source is ingested, defaults to `SourceDescriptor::enabled_by_default`
+    pub enabled: Option<bool>,
+    /// Whether the build fails if the source cannot be fetched, defaults to
+    /// `SourceDescriptor::required_by_default`
+    pub required: Option<bool>,
+    /// Seconds after which fetching is abandoned
+    pub timeout: Option<u64>,
+    /// Options specific to the source, see `SourceDescriptor::options`
+    #[serde(flatten)]
+    pub options: BTreeMap<String, toml::Value>,
+    /// Options given on the command line, taking precedence over `options`
+    #[serde(skip)]
+    pub overrides: BTreeMap<String, toml::Value>,
+}
+
+impl SourcesConfig {
+    pub fn snapshot_cache(&self) -> SnapshotCache {
+        SnapshotCache::new(
+            self.cache_dir
+                .clone()
+                .unwrap_or_else(SnapshotCache::default_dir),
+            self.offline,
+        )
+    }
+
+    /// Configuration of a source, created if it is not configured yet.
+    pub fn source_mut(&mut self, name: &str) -> &mut SourceConfig {
+        self.sources
+            .entry(registry::normalize_name(name))
+            .or_default()
+    }
+
+    /// Check that all configured and selected sources exist.
+    pub fn validate(&self, registry: &SourceRegistry) -> Result<(), Box<dyn Error>> {
+        for name in self.sources.keys().chain(self.selected.iter().flatten()) {
+            registry.get(name)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_enabled(&self, descriptor: &SourceDescriptor) -> bool {
+        match &self.selected {
+            Some(selected) => selected.iter().any(|x| x == descriptor.name),
+            None => self
+                .sources
+                .get(descriptor.name)
+                .and_then(|x| x.enabled)
+                .unwrap_or(descriptor.enabled_by_default),
+        }
+    }
+
+    pub fn policy(&self, descriptor: &SourceDescriptor) -> SourcePolicy {
+        let default = descriptor.default_policy();
+        let Some(config) = self.sources.get(descriptor.name) else {
+            return default;
+        };
+        SourcePolicy {
+            required: config.required.unwrap_or(default.required),
+            timeout: config
+                .timeout
+                .map(Duration::from_secs)
+                .unwrap_or(default.timeout),
+        }
+    }
+
+    /// Options of a source, from the config file and command line.
+    pub fn options(&self, name: &str) -> SourceOptions {
+        let mut options = SourceOptions::new();
+        if let Some(config) = self.sources.get(name) {
+            for (key, value) in &config.options {
+                options.set(key, value.clone(), self.config_dir.as_deref());
+            }
+            for (key, value) in &config.overrides {
+                options.set(key, value.clone(), None);
+            }
+        }
+        options
+    }
+}
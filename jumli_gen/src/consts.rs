@@ -1 +0,0 @@
-pub const LATEST_RIMWORLD_RELEASE: &'static str = "1.6";
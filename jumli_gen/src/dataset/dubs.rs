@@ -0,0 +1,514 @@
+use std::error::Error;
+
+use chrono::NaiveDate;
+
+use crate::{
+    dataset::LocalNotice,
+    modlist::parse_workshop_url,
+    records::types::{Certainty, Notice},
+};
+
+/// A mod listed in the Dubs Mods Discord community's slow mods spreadsheet.
+#[derive(Debug)]
+pub struct SheetMod {
+    /// 1-based row of the CSV export
+    pub row: usize,
+    pub name: String,
+    pub workshop_id: u64,
+    /// Notice derived from the row, or why there is none, e.g. because the mod is outdated
+    pub notice: Result<LocalNotice, String>,
+}
+
+/// Contents of a CSV export of the spreadsheet.
+#[derive(Debug)]
+pub struct Sheet {
+    pub mods: Vec<SheetMod>,
+    /// Rows that could not be imported or were imported with guesses
+    pub warnings: Vec<String>,
+    /// Convention used for numeric dates that are valid either way, such as 03/04/2024
+    pub date_order: DateOrder,
+}
+
+/// Order of day and month in numeric dates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DateOrder {
+    DayFirst,
+    MonthFirst,
+}
+
+/// Columns of the spreadsheet, by index.
+#[derive(Default)]
+struct Columns {
+    name: Option<usize>,
+    link: Option<usize>,
+    impact: Option<usize>,
+    notes: Option<usize>,
+    date: Option<usize>,
+    status: Option<usize>,
+}
+
+impl Columns {
+    /// Recognize a header row. The sheet has seen its columns renamed, so headers are matched by
+    /// keywords; earlier kinds take precedence, e.g. `Mod Link` is the link and not the name.
+    fn from_header(row: &csv::StringRecord) -> Option<Self> {
+        let mut columns = Self::default();
+        for (idx, header) in row.iter().enumerate() {
+            let header = header.trim().to_lowercase();
+            let has = |keywords: &[&str]| keywords.iter().any(|x| header.contains(x));
+            let column = if has(&["link", "url", "workshop", "steam"]) {
+                &mut columns.link
+            } else if has(&["date", "tested", "checked", "updated"]) {
+                &mut columns.date
+            } else if has(&["status", "state"]) {
+                &mut columns.status
+            } else if has(&["note", "comment", "detail", "description", "explanation"]) {
+                &mut columns.notes
+            } else if has(&["impact", "performance", "verdict", "rating", "tps"]) {
+                &mut columns.impact
+            } else if has(&["mod", "name"]) {
+                &mut columns.name
+            } else {
+                continue;
+            };
+            column.get_or_insert(idx);
+        }
+
+        (columns.name.is_some() && columns.impact.is_some()).then_some(columns)
+    }
+}
+
+/// A date as written in the sheet, before ambiguous numeric dates are resolved.
+enum SheetDate {
+    Exact(NaiveDate),
+    /// Valid both ways, with the day-first and month-first reading
+    Ambiguous(NaiveDate, NaiveDate),
+}
+
+/// Parse a CSV export of the spreadsheet. Rows above the header, such as titles, are skipped.
+/// Dates later than `today` are considered misread.
+pub fn parse(csv: &str, today: NaiveDate) -> Result<Sheet, Box<dyn Error>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(csv.as_bytes());
+
+    let mut columns = None;
+    let mut warnings = Vec::new();
+    // Rows with their unresolved date
+    let mut rows = Vec::new();
+    let (mut day_first, mut month_first) = (0, 0);
+    for (idx, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| format!("Unable to parse CSV: {e}"))?;
+        let row = idx + 1;
+        let Some(columns) = &columns else {
+            columns = Columns::from_header(&record);
+            continue;
+        };
+
+        let cell = |column: Option<usize>| {
+            column
+                .and_then(|x| record.get(x))
+                .map(str::trim)
+                .unwrap_or_default()
+        };
+        let name = cell(columns.name);
+        if name.is_empty() {
+            continue;
+        }
+        // Links are sometimes pasted into other cells, the link column is preferred
+        let Some(workshop_id) = columns
+            .link
+            .into_iter()
+            .chain(0..record.len())
+            .find_map(|x| parse_workshop_url(cell(Some(x))))
+        else {
+            warnings.push(format!("Row {row}: {name} has no workshop link, skipped"));
+            continue;
+        };
+
+        let date = match cell(columns.date) {
+            "" => None,
+            text => match parse_date(text, today) {
+                Some(date) => {
+                    // Unambiguous numeric dates reveal the convention of their author
+                    match numeric_order(text) {
+                        Some(DateOrder::DayFirst) => day_first += 1,
+                        Some(DateOrder::MonthFirst) => month_first += 1,
+                        None => {}
+                    }
+                    Some((text.to_string(), date))
+                }
+                None => {
+                    warnings.push(format!(
+                        "Row {row}: Unable to parse date {text:?} of {name}, left empty"
+                    ));
+                    None
+                }
+            },
+        };
+
+        let notice = classify(
+            cell(columns.impact),
+            cell(columns.notes),
+            cell(columns.status),
+        );
+        rows.push((row, name.to_string(), workshop_id, date, notice));
+    }
+
+    if columns.is_none() {
+        return Err(
+            "No header row found, expected columns for the mod name and its impact.".into(),
+        );
+    }
+
+    let date_order = if month_first > day_first {
+        DateOrder::MonthFirst
+    } else {
+        DateOrder::DayFirst
+    };
+    let mut mods = Vec::new();
+    for (row, name, workshop_id, date, notice) in rows {
+        let date = date.map(|(text, date)| match date {
+            SheetDate::Exact(date) => date,
+            SheetDate::Ambiguous(day_first, month_first) => {
+                let date = match date_order {
+                    DateOrder::DayFirst => day_first,
+                    DateOrder::MonthFirst => month_first,
+                };
+                warnings.push(format!(
+                    "Row {row}: Ambiguous date {text:?} of {name} read as {date}"
+                ));
+                date
+            }
+        });
+
+        mods.push(SheetMod {
+            row,
+            name,
+            workshop_id,
+            notice: notice.map(|(notice, certainty)| LocalNotice {
+                date,
+                notice,
+                certainty,
+                context_url: None,
+                historical: false,
+            }),
+        });
+    }
+
+    Ok(Sheet {
+        mods,
+        warnings,
+        date_order,
+    })
+}
+
+/// Derive a notice from the impact, notes and status of a row. Performance notices read like the
+/// sheet, the impact followed by the notes.
+fn classify(impact: &str, notes: &str, status: &str) -> Result<(Notice, Certainty), String> {
+    let lower = format!("{impact} {status}").to_lowercase();
+    let has = |keywords: &[&str]| keywords.iter().any(|x| lower.contains(x));
+
+    if has(&["outdated", "abandoned", "obsolete"]) {
+        return Err("marked outdated".into());
+    }
+    if impact.is_empty() {
+        return Err("no impact given".into());
+    }
+    let verdict = impact.trim_end_matches('.').to_lowercase();
+    if matches!(
+        verdict.as_str(),
+        "none" | "fine" | "ok" | "negligible" | "optimized"
+    ) || verdict.starts_with("no impact")
+        || verdict.starts_with("no tps")
+    {
+        return Err("no performance impact".into());
+    }
+
+    let mut text = impact.trim_end_matches('.').to_string();
+    text.push('.');
+    if !notes.is_empty() {
+        text.push(' ');
+        text.push_str(notes);
+    }
+
+    // The sheet's verdicts are results of profiling, unless marked as a guess
+    let certainty = if has(&["untested", "unknown", "unverified"]) {
+        Certainty::Low
+    } else if has(&["retest", "unconfirmed", "needs testing", "?"]) {
+        Certainty::Medium
+    } else {
+        Certainty::High
+    };
+
+    if has(&["unstable", "error", "crash", "break", "corrupt"]) {
+        Ok((Notice::Unstable(Some(text)), certainty))
+    } else {
+        Ok((Notice::BadPerformance(Some(text)), certainty))
+    }
+}
+
+fn is_numeric(text: &str) -> bool {
+    text.chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '/' | '.' | '-'))
+}
+
+/// Split a numeric date like `23/09/2024` into its parts, years of two digits being in this century.
+fn numeric_parts(text: &str) -> Option<(u32, u32, i32)> {
+    let parts: Vec<&str> = text.split(['/', '.', '-']).collect();
+    let [first, second, year] = parts[..] else {
+        return None;
+    };
+    // ISO dates are not ambiguous
+    if first.len() == 4 {
+        return None;
+    }
+    let year: i32 = year.parse().ok()?;
+    let year = if year < 100 { year + 2000 } else { year };
+    Some((first.parse().ok()?, second.parse().ok()?, year))
+}
+
+/// Order of a numeric date that can only be read one way.
+fn numeric_order(text: &str) -> Option<DateOrder> {
+    let (first, second, _) = numeric_parts(text)?;
+    match (first > 12, second > 12) {
+        (true, false) => Some(DateOrder::DayFirst),
+        (false, true) => Some(DateOrder::MonthFirst),
+        _ => None,
+    }
+}
+
+/// Parse the date formats found in the sheet: ISO dates, numeric dates in either order and dates
+/// with month names. A trailing time is ignored.
+fn parse_date(text: &str, today: NaiveDate) -> Option<SheetDate> {
+    let text = text.trim();
+    let valid = |date: NaiveDate| date <= today;
+
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(text, "%Y/%m/%d"))
+    {
+        return Some(SheetDate::Exact(date)).filter(|_| valid(date));
+    }
+
+    if is_numeric(text) {
+        let (first, second, year) = numeric_parts(text)?;
+        let day_first = NaiveDate::from_ymd_opt(year, second, first).filter(|x| valid(*x));
+        let month_first = NaiveDate::from_ymd_opt(year, first, second).filter(|x| valid(*x));
+        return match (day_first, month_first) {
+            (Some(a), Some(b)) if a != b => Some(SheetDate::Ambiguous(a, b)),
+            (Some(date), _) | (None, Some(date)) => Some(SheetDate::Exact(date)),
+            (None, None) => None,
+        };
+    }
+
+    // %B also accepts abbreviated month names
+    const FORMATS: &[&str] = &["%d %B %Y", "%B %d, %Y", "%B %d %Y", "%d-%B-%Y", "%d-%B-%y"];
+    let without_ordinals = strip_ordinals(text);
+    if let Some(date) = FORMATS
+        .iter()
+        .find_map(|x| NaiveDate::parse_from_str(&without_ordinals, x).ok())
+    {
+        return Some(SheetDate::Exact(date)).filter(|_| valid(date));
+    }
+
+    // Timestamps, e.g. `2024-09-23 14:02:11`
+    let (date, _) = text.split_once(' ')?;
+    parse_date(date, today)
+}
+
+/// Remove ordinal suffixes from day numbers, e.g. `23rd September 2024`.
+fn strip_ordinals(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| {
+            let trimmed = word.trim_end_matches(',');
+            match ["st", "nd", "rd", "th"]
+                .iter()
+                .find_map(|x| trimmed.strip_suffix(x))
+            {
+                Some(number)
+                    if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) =>
+                {
+                    word.replacen(trimmed, number, 1)
+                }
+                _ => word.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHEET: &str = include_str!("../../fixtures/dubs/slow_mods.csv");
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn sheet_mod<'a>(sheet: &'a Sheet, name: &str) -> &'a SheetMod {
+        sheet.mods.iter().find(|x| x.name == name).unwrap()
+    }
+
+    fn notice_date(sheet: &Sheet, name: &str) -> Option<NaiveDate> {
+        sheet_mod(sheet, name).notice.as_ref().unwrap().date
+    }
+
+    #[test]
+    fn parses_sheet() {
+        let sheet = parse(SHEET, date("2025-10-01")).unwrap();
+        // The title row is skipped and the row without a link is reported
+        assert_eq!(sheet.mods.len(), 24);
+        assert_eq!(sheet.mods[0].row, 3);
+        assert_eq!(sheet.mods[0].name, "A RimWorld of Magic");
+        assert_eq!(sheet.mods[0].workshop_id, 1201382956);
+        assert!(
+            sheet
+                .warnings
+                .contains(&"Row 27: Mod Without Link has no workshop link, skipped".into())
+        );
+
+        let notice = sheet_mod(&sheet, "Misc. Robots").notice.as_ref().unwrap();
+        assert!(matches!(
+            &notice.notice,
+            Notice::BadPerformance(Some(text)) if text == "Likely to lower TPS. Make vanilla jobs worse"
+        ));
+        assert!(matches!(notice.certainty, Certainty::High));
+
+        let unstable = sheet_mod(&sheet, "Example Unstable Mod")
+            .notice
+            .as_ref()
+            .unwrap();
+        assert!(matches!(unstable.notice, Notice::Unstable(_)));
+        assert!(matches!(unstable.certainty, Certainty::Medium));
+
+        assert_eq!(
+            sheet_mod(&sheet, "Rim War").notice.as_ref().unwrap_err(),
+            "marked outdated"
+        );
+        assert_eq!(
+            sheet_mod(&sheet, "Example Fine Mod")
+                .notice
+                .as_ref()
+                .unwrap_err(),
+            "no performance impact"
+        );
+    }
+
+    #[test]
+    fn resolves_dates() {
+        let sheet = parse(SHEET, date("2025-10-01")).unwrap();
+        // 23/09/2024, 29/02/2024, 30/09/2024 and 24/11/2023 outnumber 2/29/2024 and 11/18/2023
+        assert_eq!(sheet.date_order, DateOrder::DayFirst);
+
+        assert_eq!(
+            notice_date(&sheet, "A RimWorld of Magic"),
+            Some(date("2024-09-23"))
+        );
+        assert_eq!(
+            notice_date(&sheet, "Misc. Robots"),
+            Some(date("2024-02-29"))
+        );
+        assert_eq!(notice_date(&sheet, "PawnMorpher"), Some(date("2024-02-29")));
+        assert_eq!(
+            notice_date(&sheet, "Example Unstable Mod"),
+            Some(date("2025-09-02"))
+        );
+        assert_eq!(notice_date(&sheet, "RimHUD"), Some(date("2023-06-12")));
+        assert_eq!(
+            notice_date(&sheet, "Example Slow Mod"),
+            Some(date("2025-04-03"))
+        );
+        assert!(sheet.warnings.contains(
+            &"Row 24: Ambiguous date \"03/04/2025\" of Example Slow Mod read as 2025-04-03".into()
+        ));
+    }
+
+    #[test]
+    fn future_dates_are_misread() {
+        // The day-first reading of 03/04/2025 lies in the future, the month-first one does not
+        let sheet = parse(SHEET, date("2025-03-10")).unwrap();
+        assert_eq!(
+            notice_date(&sheet, "Example Slow Mod"),
+            Some(date("2025-03-04"))
+        );
+        assert_eq!(notice_date(&sheet, "Example Unstable Mod"), None);
+        assert!(
+            sheet
+                .warnings
+                .iter()
+                .any(|x| x.starts_with("Row 25: Unable to parse date"))
+        );
+    }
+
+    #[test]
+    fn month_first_sheets() {
+        let sheet = parse(
+            "Mod,Link,Impact,Date\n\
+             A,https://steamcommunity.com/sharedfiles/filedetails/?id=1,Slow,9/23/2024\n\
+             B,https://steamcommunity.com/sharedfiles/filedetails/?id=2,Slow,03/04/2024\n",
+            date("2025-01-01"),
+        )
+        .unwrap();
+        assert_eq!(sheet.date_order, DateOrder::MonthFirst);
+        assert_eq!(notice_date(&sheet, "B"), Some(date("2024-03-04")));
+    }
+
+    #[test]
+    fn reads_date_formats() {
+        let today = date("2025-10-01");
+        let exact = |text| match parse_date(text, today) {
+            Some(SheetDate::Exact(date)) => Some(date),
+            _ => None,
+        };
+        assert_eq!(exact("2024-09-23"), Some(date("2024-09-23")));
+        assert_eq!(exact("2024-09-23 14:02:11"), Some(date("2024-09-23")));
+        assert_eq!(exact("23/09/2024"), Some(date("2024-09-23")));
+        assert_eq!(exact("9/23/24"), Some(date("2024-09-23")));
+        assert_eq!(exact("September 2nd, 2025"), Some(date("2025-09-02")));
+        assert_eq!(exact("23rd Sep 2024"), Some(date("2024-09-23")));
+        assert!(matches!(
+            parse_date("03/04/2025", today),
+            Some(SheetDate::Ambiguous(a, b)) if a == date("2025-04-03") && b == date("2025-03-04")
+        ));
+        // Both readings are the same day
+        assert_eq!(exact("04/04/2025"), Some(date("2025-04-04")));
+        assert!(parse_date("31/31/2024", today).is_none());
+        assert!(parse_date("soon", today).is_none());
+    }
+
+    #[test]
+    fn numeric_dates_reveal_their_order() {
+        assert_eq!(numeric_order("29/02/2024"), Some(DateOrder::DayFirst));
+        assert_eq!(numeric_order("2/29/2024"), Some(DateOrder::MonthFirst));
+        assert_eq!(numeric_order("03/04/2025"), None);
+        assert_eq!(numeric_order("2024-09-23"), None);
+    }
+
+    #[test]
+    fn maps_columns_by_keywords() {
+        let header = csv::StringRecord::from(vec![
+            "Workshop URL",
+            "Performance",
+            "Mod",
+            "Last Checked",
+            "Comments",
+        ]);
+        let columns = Columns::from_header(&header).unwrap();
+        assert_eq!(columns.link, Some(0));
+        assert_eq!(columns.impact, Some(1));
+        assert_eq!(columns.name, Some(2));
+        assert_eq!(columns.date, Some(3));
+        assert_eq!(columns.notes, Some(4));
+        assert_eq!(columns.status, None);
+
+        // `Mod Link` is a link rather than the name
+        let header = csv::StringRecord::from(vec!["Mod Link", "Mod Name", "Impact"]);
+        let columns = Columns::from_header(&header).unwrap();
+        assert_eq!((columns.link, columns.name), (Some(0), Some(1)));
+
+        let title = csv::StringRecord::from(vec!["Dubs Mods community slow mods list", ""]);
+        assert!(Columns::from_header(&title).is_none());
+    }
+}
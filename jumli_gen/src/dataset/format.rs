@@ -0,0 +1,313 @@
+use std::{cell::Cell, error::Error, fmt::Write, sync::LazyLock};
+
+use clap::ValueEnum;
+use ron::ser::PrettyConfig;
+use serde::Serialize;
+
+use crate::dataset::{
+    DatasetFile, DatasetFileRecord, RON_OPTIONS, RawDataset,
+    scan::{RecordLocation, scan},
+};
+
+const INDENT: &str = "    ";
+
+/// Serializes values on a single line, with spaces between tuple members.
+static INLINE_CONFIG: LazyLock<PrettyConfig> = LazyLock::new(|| {
+    PrettyConfig::new()
+        .new_line("")
+        .indentor("")
+        .separate_tuple_members(false)
+});
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordOrder {
+    /// By the comment preceding each record, which is usually the mod name
+    Name,
+    /// By the lowest identifier of each record
+    Identifier,
+}
+
+/// A formatted dataset.
+pub struct Formatted {
+    pub contents: String,
+    /// Comments that could not be attached to the dataset or a record and were dropped
+    pub dropped_comments: usize,
+}
+
+/// Rewrite a dataset into the canonical layout. Comments preceding the dataset and records, comments
+/// after the last record and comments trailing identifiers and notice fields are kept, other
+/// comments are dropped. Notices are sorted by date, records optionally by `order`.
+pub fn format(raw: &RawDataset, order: Option<RecordOrder>) -> Result<Formatted, Box<dyn Error>> {
+    let dataset = raw.parse().map_err(|e| format!("{}:{e}", raw.name))?;
+    let layout = scan(&raw.contents);
+    if layout.records.len() != dataset.records.len() {
+        return Err(format!(
+            "{}: Found {} records while scanning, but parsed {}",
+            raw.name,
+            layout.records.len(),
+            dataset.records.len()
+        )
+        .into());
+    }
+
+    let mut records: Vec<(RecordLocation, &DatasetFileRecord)> =
+        layout.records.into_iter().zip(&dataset.records).collect();
+    match order {
+        Some(RecordOrder::Name) => records.sort_by_cached_key(|(location, _)| {
+            location
+                .comments
+                .first()
+                .map(|x| x.to_lowercase())
+                .unwrap_or_default()
+        }),
+        Some(RecordOrder::Identifier) => {
+            records.sort_by_cached_key(|(_, record)| record.identifiers.iter().min().cloned())
+        }
+        None => {}
+    }
+
+    let records: Vec<_> = records
+        .iter()
+        .map(|(location, record)| (location, *record))
+        .collect();
+    let (out, kept_comments) = write(
+        &dataset.name,
+        &dataset.description,
+        &layout.header_comments,
+        &records,
+        &layout.trailing_comments,
+    )?;
+
+    // Guard against the formatter changing the meaning of the dataset
+    let reparsed: DatasetFile = RON_OPTIONS.from_str(&out)?;
+    if !same_content(&reparsed, &dataset)? {
+        return Err(format!("{}: Formatting changed the dataset's content", raw.name).into());
+    }
+
+    Ok(Formatted {
+        contents: out,
+        dropped_comments: layout.comment_count - kept_comments,
+    })
+}
+
+/// Write a dataset in the canonical layout, returning it along with the number of comments written.
+pub fn write(
+    name: &str,
+    description: &str,
+    header_comments: &[String],
+    records: &[(&RecordLocation, &DatasetFileRecord)],
+    trailing_comments: &[String],
+) -> Result<(String, usize), Box<dyn Error>> {
+    let mut out = String::new();
+    let mut kept_comments = header_comments.len();
+    for comment in header_comments {
+        writeln!(out, "// {comment}")?;
+    }
+    writeln!(out, "Dataset(")?;
+    writeln!(out, "{INDENT}name: {},", inline(&name)?)?;
+    writeln!(out, "{INDENT}description: {},", inline(&description)?)?;
+    writeln!(out)?;
+    writeln!(out, "{INDENT}records: [")?;
+    for (location, record) in records {
+        kept_comments += write_record(&mut out, location, record)?;
+    }
+    kept_comments += trailing_comments.len();
+    for comment in trailing_comments {
+        writeln!(out, "{INDENT}{INDENT}// {comment}")?;
+    }
+    writeln!(out, "{INDENT}],")?;
+    writeln!(out, ")")?;
+
+    Ok((out, kept_comments))
+}
+
+/// Write a record, returning the number of comments written.
+fn write_record(
+    out: &mut String,
+    location: &RecordLocation,
+    record: &DatasetFileRecord,
+) -> Result<usize, Box<dyn Error>> {
+    let comment_count = Cell::new(location.comments.len());
+    let trailing = |comment: Option<&String>| match comment {
+        Some(comment) => {
+            comment_count.set(comment_count.get() + 1);
+            format!(" // {comment}")
+        }
+        None => String::new(),
+    };
+
+    let indent = INDENT.repeat(2);
+    for comment in &location.comments {
+        writeln!(out, "{indent}// {comment}")?;
+    }
+    writeln!(out, "{indent}(")?;
+
+    writeln!(out, "{indent}{INDENT}identifiers: [")?;
+    for (idx, identifier) in record.identifiers.iter().enumerate() {
+        writeln!(
+            out,
+            "{indent}{INDENT}{INDENT}{},{}",
+            inline(identifier)?,
+            trailing(location.identifier_comments.get(&idx))
+        )?;
+    }
+    writeln!(out, "{indent}{INDENT}],")?;
+
+    let mut notices: Vec<_> = record.notices.iter().enumerate().collect();
+    notices.sort_by_key(|(_, x)| x.date);
+
+    writeln!(out, "{indent}{INDENT}notices: [")?;
+    for (idx, notice) in notices {
+        let indent = format!("{indent}{INDENT}{INDENT}");
+        writeln!(
+            out,
+            "{indent}({}",
+            trailing(location.notice_comments.get(&(idx, String::new())))
+        )?;
+
+        let mut field = |name: &str, value: String| {
+            let comment = trailing(location.notice_comments.get(&(idx, name.to_string())));
+            writeln!(out, "{indent}{INDENT}{name}: {value},{comment}")
+        };
+        if let Some(date) = notice.date {
+            field("date", inline(&date)?)?;
+        }
+        field("notice", inline(&notice.notice)?)?;
+        field("certainty", inline(&notice.certainty)?)?;
+        if let Some(context_url) = &notice.context_url {
+            field("context_url", inline(context_url)?)?;
+        }
+        if notice.historical {
+            field("historical", "true".into())?;
+        }
+        writeln!(out, "{indent}),")?;
+    }
+    writeln!(out, "{indent}{INDENT}],")?;
+
+    writeln!(out, "{indent}),")?;
+    Ok(comment_count.get())
+}
+
+/// Serialize a value as RON on a single line.
+pub fn inline(value: &impl Serialize) -> Result<String, ron::Error> {
+    let ron = RON_OPTIONS.to_string_pretty(value, INLINE_CONFIG.clone())?;
+    Ok(unescape_apostrophes(&ron))
+}
+
+/// Undo the escaping of apostrophes, which RON applies although strings are delimited by double
+/// quotes, so that e.g. `didn't` stays as written.
+fn unescape_apostrophes(ron: &str) -> String {
+    let mut out = String::with_capacity(ron.len());
+    let mut chars = ron.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\'') => out.push('\''),
+            Some(next) => {
+                out.push(c);
+                out.push(next);
+            }
+            None => out.push(c),
+        }
+    }
+    out
+}
+
+fn same_content(a: &DatasetFile, b: &DatasetFile) -> Result<bool, Box<dyn Error>> {
+    let normalize = |dataset: &DatasetFile| -> Result<serde_json::Value, serde_json::Error> {
+        let mut value = serde_json::to_value(dataset)?;
+        // Records and notices may be reordered
+        if let Some(records) = value["records"].as_array_mut() {
+            for record in records.iter_mut() {
+                if let Some(notices) = record["notices"].as_array_mut() {
+                    notices.sort_by_key(|x| x.to_string());
+                }
+            }
+            records.sort_by_key(|x| x.to_string());
+        }
+        Ok(value)
+    };
+
+    Ok(normalize(a)? == normalize(b)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataset::DatasetFormat;
+
+    const DATASET: &str = r#"// Header comment
+Dataset(
+    name: "Test",
+    description: "A dataset that didn't exist",
+    records: [
+        // Example Mod
+        (
+            identifiers: [
+                PackageId("author.example"),
+                WorkshopId(1234), // Workshop page
+            ],
+            notices: [
+                (
+                    notice: Miscellaneous("Don't use the \"fast\" mode, it's broken \\ slow"),
+                    certainty: Low, // Untested
+                ),
+                (
+                    date: "2024-01-02",
+                    notice: UseAlternative("Author's Mod", 5678, None),
+                    certainty: High,
+                ),
+            ],
+        ),
+        // Trailing comment
+    ],
+)
+"#;
+
+    fn raw(contents: &str) -> RawDataset {
+        RawDataset {
+            name: "test.ron".into(),
+            format: DatasetFormat::Ron,
+            contents: contents.into(),
+        }
+    }
+
+    #[test]
+    fn apostrophes_are_not_escaped() {
+        assert_eq!(inline(&"didn't").unwrap(), r#""didn't""#);
+        assert_eq!(inline(&r"a\'b").unwrap(), r#""a\\'b""#);
+        assert_eq!(inline(&"\"quoted\"").unwrap(), r#""\"quoted\"""#);
+    }
+
+    #[test]
+    fn round_trip_keeps_content_and_comments() {
+        let formatted = format(&raw(DATASET), None).unwrap();
+        assert_eq!(formatted.dropped_comments, 0);
+        assert!(!formatted.contents.contains(r"\'"));
+        assert!(formatted.contents.contains("didn't exist"));
+        assert!(formatted.contents.contains("Author's Mod"));
+        for comment in [
+            "// Header comment",
+            "// Example Mod",
+            "// Workshop page",
+            "// Untested",
+            "// Trailing comment",
+        ] {
+            assert!(
+                formatted.contents.contains(comment),
+                "{comment} was dropped"
+            );
+        }
+
+        let original = raw(DATASET).parse().unwrap();
+        let reparsed = raw(&formatted.contents).parse().unwrap();
+        assert!(same_content(&original, &reparsed).unwrap());
+
+        // Formatting is idempotent
+        let again = format(&raw(&formatted.contents), None).unwrap();
+        assert_eq!(again.contents, formatted.contents);
+    }
+}
@@ -0,0 +1,416 @@
+use std::{collections::HashMap, fmt::Display};
+
+use chrono::NaiveDate;
+use url::Url;
+
+use crate::{
+    dataset::{DatasetFileRecord, DatasetFormat, LocalNotice, RawDataset, scan::scan},
+    records::types::{ModIdentifier, Notice},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem found in a dataset file.
+#[derive(Debug, Clone)]
+pub struct LintMessage {
+    pub file: String,
+    pub line: usize,
+    pub column: Option<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Display for LintMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ":{column}")?;
+        }
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, ": {severity}: {}", self.message)
+    }
+}
+
+/// Where an identifier was used, for reporting duplicates.
+struct Occurrence {
+    dataset: usize,
+    file: String,
+    line: usize,
+}
+
+/// Check datasets for syntax errors and semantic mistakes. Notices dated after `today` are reported.
+pub fn lint(datasets: &[RawDataset], today: NaiveDate) -> Vec<LintMessage> {
+    let mut messages = Vec::new();
+    let mut occurrences: HashMap<&ModIdentifier, Vec<Occurrence>> = HashMap::new();
+    let mut parsed = Vec::new();
+
+    for raw in datasets {
+        match raw.parse() {
+            Ok(dataset) => {
+                // Record positions are only known for RON, other formats report the start of the file
+                let locations = match raw.format {
+                    DatasetFormat::Ron => scan(&raw.contents).records,
+                    _ => Vec::new(),
+                };
+                parsed.push((raw, dataset, locations))
+            }
+            Err(e) => messages.push(LintMessage {
+                file: raw.name.clone(),
+                line: e.line,
+                column: e.column,
+                severity: Severity::Error,
+                message: e.message,
+            }),
+        }
+    }
+
+    for (dataset_idx, (raw, dataset, locations)) in parsed.iter().enumerate() {
+        for (record_idx, record) in dataset.records.iter().enumerate() {
+            // Fall back to the start of the file if the scanner disagrees with the parser
+            let line = locations.get(record_idx).map_or(1, |x| x.line);
+            let mut report = |severity, message: String| {
+                messages.push(LintMessage {
+                    file: raw.name.clone(),
+                    line,
+                    column: None,
+                    severity,
+                    message,
+                })
+            };
+
+            lint_record(record, today, &mut report);
+
+            for (idx, identifier) in record.identifiers.iter().enumerate() {
+                // Repetitions within a record are reported by lint_record
+                if record.identifiers[..idx].contains(identifier) {
+                    continue;
+                }
+                occurrences.entry(identifier).or_default().push(Occurrence {
+                    dataset: dataset_idx,
+                    file: raw.name.clone(),
+                    line,
+                });
+            }
+        }
+    }
+
+    for (identifier, occurrences) in occurrences {
+        let Some((first, others)) = occurrences.split_first() else {
+            continue;
+        };
+        for other in others {
+            let (severity, scope) = if other.dataset == first.dataset {
+                (Severity::Error, "in the same dataset")
+            } else {
+                (Severity::Warning, "in another dataset")
+            };
+            messages.push(LintMessage {
+                file: other.file.clone(),
+                line: other.line,
+                column: None,
+                severity,
+                message: format!(
+                    "Identifier {identifier} is also used {scope} at {}:{}",
+                    first.file, first.line
+                ),
+            });
+        }
+    }
+
+    messages.sort_by(|a, b| (&a.file, a.line, &a.message).cmp(&(&b.file, b.line, &b.message)));
+    messages
+}
+
+fn lint_record(
+    record: &DatasetFileRecord,
+    today: NaiveDate,
+    report: &mut impl FnMut(Severity, String),
+) {
+    if record.identifiers.is_empty() {
+        report(Severity::Error, "Record has no identifiers".into());
+    }
+    if record.notices.is_empty() {
+        report(Severity::Warning, "Record has no notices".into());
+    }
+
+    for (idx, identifier) in record.identifiers.iter().enumerate() {
+        if identifier.is_invalid() {
+            report(
+                Severity::Error,
+                format!("Invalid identifier {identifier:?}"),
+            );
+        } else if !identifier.is_redirectable() {
+            report(
+                Severity::Error,
+                format!(
+                    "Package id {identifier:?} contains characters that prevent generating a redirect page"
+                ),
+            );
+        }
+
+        if record.identifiers[..idx].contains(identifier) {
+            report(
+                Severity::Warning,
+                format!("Identifier {identifier} is listed more than once"),
+            );
+        }
+    }
+
+    for notice in &record.notices {
+        lint_notice(record, notice, today, report);
+    }
+}
+
+fn lint_notice(
+    record: &DatasetFileRecord,
+    notice: &LocalNotice,
+    today: NaiveDate,
+    report: &mut impl FnMut(Severity, String),
+) {
+    if let Some(date) = notice.date
+        && date > today
+    {
+        report(
+            Severity::Error,
+            format!("Notice is dated in the future ({date})"),
+        );
+    }
+
+    let is_blank = |text: &str| text.trim().is_empty();
+    let empty_explanation = match &notice.notice {
+        Notice::BadPerformance(reason) | Notice::Unstable(reason) => {
+            reason.as_deref().is_some_and(is_blank)
+        }
+        Notice::UseAlternative(name, _, reason) => {
+            if is_blank(name) {
+                report(
+                    Severity::Error,
+                    "UseAlternative has an empty mod name".into(),
+                );
+            }
+            reason.as_deref().is_some_and(is_blank)
+        }
+        Notice::Bug(text) | Notice::Miscellaneous(text) => is_blank(text),
+        Notice::OutOfDate => false,
+    };
+    if empty_explanation {
+        report(Severity::Error, "Notice has an empty explanation".into());
+    }
+
+    if let Notice::UseAlternative(_, Some(workshop_id), _) = &notice.notice {
+        if *workshop_id == 0 {
+            report(
+                Severity::Error,
+                "UseAlternative has an invalid workshop id".into(),
+            );
+        } else if record
+            .identifiers
+            .contains(&ModIdentifier::WorkshopId(*workshop_id))
+        {
+            report(
+                Severity::Error,
+                format!("UseAlternative recommends the mod itself ({workshop_id})"),
+            );
+        }
+    }
+
+    if let Some(context_url) = &notice.context_url {
+        match Url::parse(context_url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") && url.host().is_some() => {}
+            Ok(_) => report(
+                Severity::Error,
+                format!("context_url {context_url:?} is not an http(s) URL"),
+            ),
+            Err(e) => report(
+                Severity::Error,
+                format!("context_url {context_url:?} is malformed: {e}"),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dataset(name: &str, records: &str) -> RawDataset {
+        RawDataset {
+            name: name.into(),
+            format: DatasetFormat::Ron,
+            contents: format!(
+                "Dataset(\n    name: \"{name}\",\n    description: \"\",\n    records: [\n{records}    ],\n)\n"
+            ),
+        }
+    }
+
+    /// A record on its own line, followed by a trailing comma.
+    fn record(identifiers: &str, notice: &str) -> String {
+        format!(
+            "        (identifiers: [{identifiers}], notices: [(date: \"2024-01-01\", {notice}, certainty: High)]),\n"
+        )
+    }
+
+    fn lint_one(records: &[String]) -> Vec<(usize, Severity, String)> {
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        lint(&[dataset("a.ron", &records.concat())], today)
+            .into_iter()
+            .map(|x| (x.line, x.severity, x.message))
+            .collect()
+    }
+
+    const LAG: &str = "notice: BadPerformance(\"Lags\")";
+
+    #[test]
+    fn clean_datasets_pass() {
+        assert!(
+            lint_one(&[
+                record("WorkshopId(1)", LAG),
+                record("PackageId(\"a.b\")", LAG)
+            ])
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        let mut raw = dataset("a.ron", "");
+        raw.contents = "Dataset(\n    name: 1,\n)".into();
+        let messages = lint(&[raw], NaiveDate::MAX);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].line, 2);
+        assert!(messages[0].column.is_some());
+        assert_eq!(messages[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn reports_duplicate_identifiers() {
+        let messages = lint_one(&[
+            record("WorkshopId(1), WorkshopId(1)", LAG),
+            record("WorkshopId(1)", LAG),
+        ]);
+        assert_eq!(
+            messages,
+            [
+                (
+                    5,
+                    Severity::Warning,
+                    "Identifier 1 is listed more than once".into()
+                ),
+                (
+                    6,
+                    Severity::Error,
+                    "Identifier 1 is also used in the same dataset at a.ron:5".into()
+                ),
+            ]
+        );
+
+        let today = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let messages = lint(
+            &[
+                dataset("a.ron", &record("PackageId(\"a.b\")", LAG)),
+                dataset("b.ron", &record("PackageId(\"a.b\")", LAG)),
+            ],
+            today,
+        );
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].file, "b.ron");
+        assert_eq!(messages[0].severity, Severity::Warning);
+        assert_eq!(
+            messages[0].message,
+            "Identifier a.b is also used in another dataset at a.ron:5"
+        );
+    }
+
+    #[test]
+    fn reports_future_dates() {
+        let messages =
+            lint_one(&[record("WorkshopId(1)", LAG).replace("2024-01-01", "2025-01-02")]);
+        assert_eq!(
+            messages,
+            [(
+                5,
+                Severity::Error,
+                "Notice is dated in the future (2025-01-02)".into()
+            )]
+        );
+        // Notices of today are fine
+        assert!(
+            lint_one(&[record("WorkshopId(1)", LAG).replace("2024-01-01", "2025-01-01")])
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn reports_empty_explanations() {
+        for notice in [
+            "notice: BadPerformance(\" \")",
+            "notice: Unstable(\"\")",
+            "notice: Bug(\"\")",
+            "notice: Miscellaneous(\"  \")",
+            "notice: UseAlternative(\"Other\", None, \"\")",
+        ] {
+            assert_eq!(
+                lint_one(&[record("WorkshopId(1)", notice)]),
+                [(5, Severity::Error, "Notice has an empty explanation".into())],
+                "{notice}"
+            );
+        }
+        // Explanations may be left out
+        assert!(lint_one(&[record("WorkshopId(1)", "notice: BadPerformance(None)")]).is_empty());
+    }
+
+    #[test]
+    fn reports_malformed_context_urls() {
+        let with_url =
+            |url: &str| record("WorkshopId(1)", &format!("{LAG}, context_url: \"{url}\""));
+        assert!(lint_one(&[with_url("https://example.com/report")]).is_empty());
+
+        let messages = lint_one(&[with_url("not a url")]);
+        assert_eq!(messages.len(), 1);
+        assert!(
+            messages[0]
+                .2
+                .starts_with("context_url \"not a url\" is malformed")
+        );
+        assert_eq!(
+            lint_one(&[with_url("ftp://example.com/report")]),
+            [(
+                5,
+                Severity::Error,
+                "context_url \"ftp://example.com/report\" is not an http(s) URL".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_self_recommendations() {
+        let messages = lint_one(&[record(
+            "WorkshopId(1)",
+            "notice: UseAlternative(\"Itself\", Some(1), None)",
+        )]);
+        assert_eq!(
+            messages,
+            [(
+                5,
+                Severity::Error,
+                "UseAlternative recommends the mod itself (1)".into()
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_package_ids_unfit_for_redirects() {
+        for package_id in ["a/b", ".", "..", "a\\u{7}b"] {
+            let messages = lint_one(&[record(&format!("PackageId(\"{package_id}\")"), LAG)]);
+            assert_eq!(messages.len(), 1, "{package_id}");
+            assert!(messages[0].2.contains("prevent generating a redirect page"));
+        }
+    }
+}
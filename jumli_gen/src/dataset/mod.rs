@@ -0,0 +1,355 @@
+use std::{fmt::Display, path::Path, sync::LazyLock};
+
+use chrono::NaiveDate;
+use ron::extensions::Extensions;
+use schemars::{JsonSchema, Schema, schema_for};
+use serde::{Deserialize, Serialize};
+
+use crate::records::types::{
+    Certainty, IngestibleData, ModIdentifier, Notice, NoticeRecord, Source,
+};
+
+pub mod dubs;
+pub mod format;
+pub mod lint;
+pub mod scan;
+
+pub static RON_OPTIONS: LazyLock<ron::Options> =
+    LazyLock::new(|| ron::Options::default().with_default_extension(Extensions::IMPLICIT_SOME));
+
+/// Serialization formats datasets can be written in, determined by file extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DatasetFormat {
+    Ron,
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl DatasetFormat {
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "ron" => Some(Self::Ron),
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// Contents of a dataset file before parsing.
+#[derive(Clone, Debug)]
+pub struct RawDataset {
+    /// File name or path, used in messages
+    pub name: String,
+    pub format: DatasetFormat,
+    pub contents: String,
+}
+
+/// Error encountered while parsing a dataset, with the position it occurred at.
+#[derive(Clone, Debug)]
+pub struct ParseError {
+    /// 1-based line
+    pub line: usize,
+    /// 1-based column, if known
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ":{column}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl RawDataset {
+    pub fn parse(&self) -> Result<DatasetFile, ParseError> {
+        match self.format {
+            DatasetFormat::Ron => RON_OPTIONS
+                .from_str(&self.contents)
+                .map_err(|e| ParseError {
+                    line: e.span.start.line,
+                    column: Some(e.span.start.col),
+                    message: e.code.to_string(),
+                }),
+            DatasetFormat::Json => serde_json::from_str(&self.contents).map_err(|e| {
+                let (line, column) = (e.line().max(1), e.column());
+                ParseError {
+                    line,
+                    column: Some(column).filter(|x| *x > 0),
+                    message: strip_position(e.to_string(), line, column),
+                }
+            }),
+            DatasetFormat::Toml => toml::from_str(&self.contents).map_err(|e| {
+                let (line, column) = e.span().map_or((1, None), |span| self.position(span.start));
+                ParseError {
+                    line,
+                    column,
+                    message: e.message().to_string(),
+                }
+            }),
+            // Enums are written as single-key maps like in JSON, instead of YAML tags
+            DatasetFormat::Yaml => serde_yaml::with::singleton_map_recursive::deserialize(
+                serde_yaml::Deserializer::from_str(&self.contents),
+            )
+            .map_err(|e: serde_yaml::Error| {
+                let (line, column) = e.location().map_or((1, 0), |x| (x.line(), x.column()));
+                ParseError {
+                    line,
+                    column: Some(column).filter(|x| *x > 0),
+                    message: strip_position(e.to_string(), line, column),
+                }
+            }),
+        }
+    }
+
+    /// Line and column of a byte offset into the contents.
+    fn position(&self, offset: usize) -> (usize, Option<usize>) {
+        let before = &self.contents[..offset.min(self.contents.len())];
+        let line_start = before.rfind('\n').map_or(0, |x| x + 1);
+        (
+            before.matches('\n').count() + 1,
+            Some(before[line_start..].chars().count() + 1),
+        )
+    }
+}
+
+/// Remove the position from an error message that already includes it, since it is reported separately.
+fn strip_position(message: String, line: usize, column: usize) -> String {
+    message.replace(&format!(" at line {line} column {column}"), "")
+}
+
+/// JSON Schema describing dataset files, for editor support and tools generating datasets.
+pub fn dataset_schema() -> Schema {
+    schema_for!(DatasetFile)
+}
+
+/// A JuMLi dataset: a named collection of records about mods.
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(rename = "Dataset")]
+pub struct DatasetFile {
+    pub name: String,
+    pub description: String,
+    pub records: Vec<DatasetFileRecord>,
+}
+
+/// Notices about a single mod.
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+pub struct DatasetFileRecord {
+    /// Package ids and workshop ids of the mod, including those of forks and continuations it applies to
+    pub identifiers: Vec<ModIdentifier>,
+    pub notices: Vec<LocalNotice>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct LocalNotice {
+    /// Date the notice was written or last confirmed
+    pub date: Option<NaiveDate>,
+    pub notice: Notice,
+    /// How sure the author is about the notice
+    pub certainty: Certainty,
+    /// Link to evidence, such as a profiler screenshot or a bug report
+    pub context_url: Option<String>,
+    /// Whether the notice no longer applies, but is kept for reference
+    #[serde(default)]
+    pub historical: bool,
+}
+
+impl From<DatasetFile> for Vec<IngestibleData> {
+    fn from(dataset: DatasetFile) -> Self {
+        dataset
+            .records
+            .into_iter()
+            .map(|entry| IngestibleData {
+                identifiers: entry.identifiers,
+                notices: entry
+                    .notices
+                    .into_iter()
+                    .map(|local| NoticeRecord {
+                        certainty: local.certainty,
+                        context_url: local.context_url,
+                        date: local.date,
+                        notice: local.notice,
+                        source: Source::JumliDataset(dataset.name.clone()),
+                        historical: local.historical,
+                        alternative_chain: None,
+                        corroborations: Vec::new(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RON: &str = r#"Dataset(
+    name: "Example",
+    description: "Written in every format",
+    records: [
+        (
+            identifiers: [PackageId("author.mod"), WorkshopId(1000000001)],
+            notices: [
+                (
+                    date: "2024-09-23",
+                    notice: BadPerformance("Lags with many pawns"),
+                    certainty: High,
+                    context_url: "https://example.com",
+                ),
+                (
+                    notice: UseAlternative("Other", Some(1000000002), Some("Updated")),
+                    certainty: Low,
+                    historical: true,
+                ),
+            ],
+        ),
+    ],
+)"#;
+
+    fn parse(format: DatasetFormat, contents: &str) -> Result<DatasetFile, ParseError> {
+        RawDataset {
+            name: "example".into(),
+            format,
+            contents: contents.into(),
+        }
+        .parse()
+    }
+
+    /// Parse a dataset and check that it matches the RON example.
+    fn assert_same_as_ron(format: DatasetFormat, contents: &str) {
+        let expected = serde_json::to_value(parse(DatasetFormat::Ron, RON).unwrap()).unwrap();
+        let dataset = parse(format, contents).unwrap();
+        assert_eq!(serde_json::to_value(dataset).unwrap(), expected);
+    }
+
+    fn assert_error_at(format: DatasetFormat, contents: &str, line: usize, column: usize) {
+        let error = parse(format, contents).unwrap_err();
+        assert_eq!((error.line, error.column), (line, Some(column)), "{error}");
+    }
+
+    #[test]
+    fn json_datasets() {
+        assert_same_as_ron(
+            DatasetFormat::Json,
+            r#"{
+    "name": "Example",
+    "description": "Written in every format",
+    "records": [
+        {
+            "identifiers": [{ "PackageId": "author.mod" }, { "WorkshopId": 1000000001 }],
+            "notices": [
+                {
+                    "date": "2024-09-23",
+                    "notice": { "BadPerformance": "Lags with many pawns" },
+                    "certainty": "High",
+                    "context_url": "https://example.com"
+                },
+                {
+                    "notice": { "UseAlternative": ["Other", 1000000002, "Updated"] },
+                    "certainty": "Low",
+                    "historical": true
+                }
+            ]
+        }
+    ]
+}"#,
+        );
+        assert_error_at(
+            DatasetFormat::Json,
+            "{\n    \"name\": \"Example\",\n    \"description\": ,\n}",
+            3,
+            20,
+        );
+    }
+
+    #[test]
+    fn toml_datasets() {
+        assert_same_as_ron(
+            DatasetFormat::Toml,
+            r#"name = "Example"
+description = "Written in every format"
+
+[[records]]
+identifiers = [{ PackageId = "author.mod" }, { WorkshopId = 1000000001 }]
+
+[[records.notices]]
+date = "2024-09-23"
+notice = { BadPerformance = "Lags with many pawns" }
+certainty = "High"
+context_url = "https://example.com"
+
+[[records.notices]]
+notice = { UseAlternative = ["Other", 1000000002, "Updated"] }
+certainty = "Low"
+historical = true
+"#,
+        );
+        assert_error_at(
+            DatasetFormat::Toml,
+            "name = \"Example\"\ndescription = \n",
+            2,
+            15,
+        );
+    }
+
+    #[test]
+    fn yaml_datasets() {
+        assert_same_as_ron(
+            DatasetFormat::Yaml,
+            r#"name: Example
+description: Written in every format
+records:
+  - identifiers:
+      - PackageId: author.mod
+      - WorkshopId: 1000000001
+    notices:
+      - date: 2024-09-23
+        notice:
+          BadPerformance: Lags with many pawns
+        certainty: High
+        context_url: https://example.com
+      - notice:
+          UseAlternative: [Other, 1000000002, Updated]
+        certainty: Low
+        historical: true
+"#,
+        );
+        assert_error_at(
+            DatasetFormat::Yaml,
+            "name: Example\ndescription: Written: in every format\n",
+            2,
+            21,
+        );
+    }
+
+    #[test]
+    fn schema_accepts_committed_datasets() {
+        let schema = serde_json::to_value(dataset_schema()).unwrap();
+        let validator = jsonschema::validator_for(&schema).unwrap();
+
+        let dataset = parse(
+            DatasetFormat::Ron,
+            include_str!("../../../jumli_data/records/performance_advice.ron"),
+        )
+        .unwrap();
+        let instance = serde_json::to_value(dataset).unwrap();
+        let errors: Vec<_> = validator
+            .iter_errors(&instance)
+            .map(|e| e.to_string())
+            .collect();
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let mut invalid = instance;
+        invalid["records"][0]["notices"][0]["certainty"] = "Certain".into();
+        assert!(!validator.is_valid(&invalid));
+    }
+}
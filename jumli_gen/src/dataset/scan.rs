@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+/// Layout information about a dataset file that the RON parser discards.
+#[derive(Debug, Clone, Default)]
+pub struct DatasetLayout {
+    /// Line comments before `Dataset(`
+    pub header_comments: Vec<String>,
+    /// One entry per element of the top-level `records` list
+    pub records: Vec<RecordLocation>,
+    /// Line comments after the last record, before the end of the `records` list
+    pub trailing_comments: Vec<String>,
+    /// Number of line comments in the file, including ones not attached to anything above
+    pub comment_count: usize,
+}
+
+/// Position and comments of a record within a dataset file.
+#[derive(Debug, Clone, Default)]
+pub struct RecordLocation {
+    /// 1-based line of the record's opening parenthesis
+    pub line: usize,
+    /// Line comments directly preceding the record, usually the mod name
+    pub comments: Vec<String>,
+    /// Comments trailing an identifier, by index of the identifier
+    pub identifier_comments: HashMap<usize, String>,
+    /// Comments trailing a notice field, by index of the notice and field name.
+    /// The field name is empty for comments trailing the notice's opening parenthesis.
+    pub notice_comments: HashMap<(usize, String), String>,
+}
+
+/// Where inside a record the scanner currently is.
+#[derive(Default)]
+struct RecordCursor {
+    list: String,
+    identifier: Option<usize>,
+    notice: Option<usize>,
+    field: String,
+}
+
+/// Find the records of a RON dataset without fully parsing it, since the parser does not track
+/// positions of values or comments.
+pub fn scan(source: &str) -> DatasetLayout {
+    let mut layout = DatasetLayout::default();
+    let mut line = 1;
+    let mut depth = 0usize;
+    // Depth at which elements of the records list start, while inside of it
+    let mut records_depth = None;
+    let mut last_ident = String::new();
+    let mut line_has_token = false;
+    let mut pending_comments = Vec::new();
+    let mut cursor = RecordCursor::default();
+
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !(c.is_whitespace() || c == '/' && matches!(chars.peek(), Some('/' | '*'))) {
+            line_has_token = true;
+        }
+        // Depth relative to the elements of the records list
+        let record_depth = records_depth.and_then(|x| depth.checked_sub(x));
+
+        match c {
+            '\n' => {
+                line += 1;
+                line_has_token = false;
+            }
+            '"' => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        // Skip escaped characters, which may be line breaks
+                        '\\' => line += usize::from(chars.next() == Some('\n')),
+                        '\n' => line += 1,
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                chars.next();
+                let mut comment = String::new();
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                    comment.push(c);
+                }
+                let comment = comment.trim().to_string();
+                layout.comment_count += 1;
+
+                if depth == 0 {
+                    layout.header_comments.push(comment);
+                } else if record_depth == Some(0) && !line_has_token {
+                    pending_comments.push(comment);
+                } else if let Some(record_depth @ 1..) = record_depth
+                    && line_has_token
+                    && let Some(record) = layout.records.last_mut()
+                {
+                    attach_trailing(record, &cursor, record_depth, comment);
+                }
+                // Comments in other places are not preserved
+
+                line += 1;
+                line_has_token = false;
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '(' | '[' | '{' => {
+                match record_depth {
+                    None if c == '[' && depth == 1 && last_ident == "records" => {
+                        records_depth = Some(depth + 1);
+                    }
+                    Some(0) if c == '(' => {
+                        layout.records.push(RecordLocation {
+                            line,
+                            comments: std::mem::take(&mut pending_comments),
+                            ..Default::default()
+                        });
+                        cursor = RecordCursor::default();
+                    }
+                    Some(1) if c == '[' => cursor.list = last_ident.clone(),
+                    Some(2) if c == '(' && cursor.list == "notices" => {
+                        cursor.notice = Some(cursor.notice.map_or(0, |x| x + 1));
+                        cursor.field.clear();
+                    }
+                    _ => {}
+                }
+                depth += 1;
+            }
+            ')' | ']' | '}' => {
+                depth = depth.saturating_sub(1);
+                if c == ']' && Some(depth + 1) == records_depth {
+                    records_depth = None;
+                    layout.trailing_comments = std::mem::take(&mut pending_comments);
+                }
+            }
+            ':' if record_depth == Some(3) => cursor.field = last_ident.clone(),
+            c if c.is_alphanumeric() || c == '_' => {
+                last_ident.clear();
+                last_ident.push(c);
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    last_ident.push(c);
+                    chars.next();
+                }
+
+                if record_depth == Some(2) && cursor.list == "identifiers" {
+                    cursor.identifier = Some(cursor.identifier.map_or(0, |x| x + 1));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    layout
+}
+
+fn attach_trailing(
+    record: &mut RecordLocation,
+    cursor: &RecordCursor,
+    record_depth: usize,
+    comment: String,
+) {
+    match cursor.list.as_str() {
+        "identifiers" if record_depth >= 2 => {
+            if let Some(identifier) = cursor.identifier {
+                record.identifier_comments.insert(identifier, comment);
+            }
+        }
+        "notices" if record_depth >= 3 => {
+            if let Some(notice) = cursor.notice {
+                record
+                    .notice_comments
+                    .insert((notice, cursor.field.clone()), comment);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{
+    modlist::{Modlist, ModlistFormat, parse_workshop_url},
+    records::types::ModIdentifier,
+};
+
+/// Whether a line looks like a mod of a RimSort clipboard export.
+pub fn is_entry(line: &str) -> bool {
+    parse_line(line).is_some()
+}
+
+/// Parse a RimSort clipboard export.
+///
+/// The export starts with a header, followed by an empty line and one `name [packageId][url]` line per mod.
+/// Local mods have no valid workshop URL.
+pub fn parse(input: &str) -> Modlist {
+    let mut modlist = Modlist::new(ModlistFormat::Clipboard);
+    let mut lines = input.lines().enumerate();
+    // Skip the header
+    for (_, line) in lines.by_ref() {
+        if line.trim().is_empty() {
+            break;
+        }
+    }
+
+    for (idx, line) in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_line(line) {
+            Some((name, identifiers)) => modlist.push(idx + 1, name, identifiers),
+            None => modlist.warn(idx + 1, line, "Expected `name [packageId][url]`"),
+        }
+    }
+
+    modlist
+}
+
+fn parse_line(line: &str) -> Option<(String, Vec<ModIdentifier>)> {
+    let line = line.trim_end().strip_suffix(']')?;
+    let (rest, url) = line.rsplit_once("][")?;
+    let (name, package_id) = rest.rsplit_once(" [")?;
+
+    let mut identifiers = Vec::new();
+    if !package_id.trim().is_empty() {
+        identifiers.push(ModIdentifier::PackageId(package_id.trim().to_string()));
+    }
+    if let Some(workshop_id) = parse_workshop_url(url) {
+        identifiers.push(ModIdentifier::WorkshopId(workshop_id));
+    }
+
+    Some((name.trim().to_string(), identifiers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rimsort_export() {
+        let modlist = parse(include_str!("../../fixtures/modlists/clipboard.txt"));
+
+        let names: Vec<_> = modlist.entries.iter().map(|x| x.name.as_str()).collect();
+        assert_eq!(names, ["Harmony", "Core", "Outdated Example"]);
+        assert_eq!(
+            modlist.entries[0].identifiers,
+            [
+                ModIdentifier::PackageId("brrainz.harmony".into()),
+                ModIdentifier::WorkshopId(2009463077)
+            ]
+        );
+        // Mods that are not on the workshop have no URL
+        assert_eq!(
+            modlist.entries[1].identifiers,
+            [ModIdentifier::PackageId("ludeon.rimworld".into())]
+        );
+        let position = modlist.entries[2].position.unwrap();
+        assert_eq!((position.line, position.index), (7, 2));
+
+        assert_eq!(modlist.warnings.len(), 1);
+        assert_eq!(modlist.warnings[0].line, 8);
+    }
+
+    #[test]
+    fn header_is_not_parsed() {
+        let modlist = parse("Harmony [brrainz.harmony][Not found]\n\nCore [ludeon.rimworld][]");
+        assert_eq!(modlist.entries.len(), 1);
+        assert_eq!(modlist.entries[0].name, "Core");
+    }
+}
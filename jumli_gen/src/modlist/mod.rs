@@ -0,0 +1,449 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::Display,
+    io::{BufRead, Read},
+};
+
+use clap::ValueEnum;
+use quick_xml::{Reader, events::Event};
+use serde::Serialize;
+
+use crate::{records::types::ModIdentifier, sources::mods_folder::ModMetadata};
+
+pub mod clipboard;
+pub mod mods_config;
+pub mod plain;
+pub mod rentry;
+pub mod rimpy;
+pub mod save;
+
+/// Appended by the game to the package id of a workshop mod that is also installed locally
+const STEAM_SUFFIX: &str = "_steam";
+
+/// A single mod taken from a modlist.
+#[derive(Debug, Clone)]
+pub struct ModlistEntry {
+    pub name: String,
+    pub identifiers: Vec<ModIdentifier>,
+    /// Where the entry was found, if it was read from a modlist
+    pub position: Option<Position>,
+}
+
+/// Position of an entry within a modlist.
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct Position {
+    /// 1-based line the entry was found on
+    pub line: usize,
+    /// 0-based position in the load order
+    pub index: usize,
+}
+
+/// A line of a modlist that could not be parsed.
+#[derive(Serialize, Debug, Clone)]
+pub struct ModlistWarning {
+    /// 1-based line
+    pub line: usize,
+    pub content: String,
+    pub message: String,
+}
+
+impl Display for ModlistWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Line {}: {}: {}", self.line, self.message, self.content)
+    }
+}
+
+#[derive(ValueEnum, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModlistFormat {
+    /// RimSort's clipboard export, one `name [packageId][url]` line per mod
+    Clipboard,
+    /// The game's ModsConfig.xml, which RimSort's XML list export uses as well
+    ModsConfig,
+    /// Saved mod lists (.rml) of RimPy and the game
+    Rimpy,
+    /// Rentry markup as exported by RimSort and RimPy
+    Rentry,
+    /// A RimWorld save (.rws), of which only the mods are read
+    Save,
+    /// One package id, workshop id or workshop URL per line
+    Plain,
+}
+
+impl ModlistFormat {
+    /// Guess the format of a modlist from its contents.
+    pub fn detect(input: &str) -> Self {
+        if input.contains("<ModsConfigData") {
+            Self::ModsConfig
+        } else if input.contains("<savegame") {
+            Self::Save
+        } else if input.contains("<savedModList") {
+            Self::Rimpy
+        } else if input.lines().any(rentry::is_entry) {
+            Self::Rentry
+        } else if input.lines().any(clipboard::is_entry) {
+            Self::Clipboard
+        } else {
+            Self::Plain
+        }
+    }
+}
+
+impl Display for ModlistFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Clipboard => write!(f, "RimSort clipboard export"),
+            Self::ModsConfig => write!(f, "ModsConfig.xml"),
+            Self::Rimpy => write!(f, "saved mod list"),
+            Self::Rentry => write!(f, "Rentry markup"),
+            Self::Save => write!(f, "save"),
+            Self::Plain => write!(f, "plain list"),
+        }
+    }
+}
+
+/// A parsed modlist.
+#[derive(Debug, Clone)]
+pub struct Modlist {
+    pub format: ModlistFormat,
+    /// Game version the list was saved with, if the format records it
+    pub game_version: Option<String>,
+    /// Expansions the game knows about whether active or not, only recorded by ModsConfig.xml
+    pub known_expansions: Vec<String>,
+    /// Mods in load order
+    pub entries: Vec<ModlistEntry>,
+    pub warnings: Vec<ModlistWarning>,
+}
+
+impl Modlist {
+    fn new(format: ModlistFormat) -> Self {
+        Self {
+            format,
+            game_version: None,
+            known_expansions: Vec::new(),
+            entries: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, line: usize, name: String, identifiers: Vec<ModIdentifier>) {
+        let index = self.entries.len();
+        self.entries.push(ModlistEntry {
+            name,
+            identifiers,
+            position: Some(Position { line, index }),
+        });
+    }
+
+    fn warn(&mut self, line: usize, content: &str, message: impl Into<String>) {
+        self.warnings.push(ModlistWarning {
+            line,
+            content: content.trim().to_string(),
+            message: message.into(),
+        });
+    }
+}
+
+/// Parse a modlist, detecting its format unless given. Package ids are resolved to workshop ids and
+/// names using the mods in `installed`, which is needed for workshop mods whose package id carries
+/// the `_steam` suffix.
+pub fn parse(
+    input: &str,
+    format: Option<ModlistFormat>,
+    installed: &[ModMetadata],
+) -> Result<Modlist, Box<dyn Error>> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let mut modlist = match format.unwrap_or_else(|| ModlistFormat::detect(input)) {
+        ModlistFormat::Clipboard => clipboard::parse(input),
+        ModlistFormat::ModsConfig => mods_config::parse(input)?,
+        ModlistFormat::Rimpy => rimpy::parse(input)?,
+        ModlistFormat::Rentry => rentry::parse(input),
+        ModlistFormat::Save => save::parse(input.as_bytes())?,
+        ModlistFormat::Plain => plain::parse(input),
+    };
+
+    resolve_installed(&mut modlist.entries, installed);
+    Ok(modlist)
+}
+
+/// Read the mods of a RimWorld save, see [`parse`]. Only the start of the save is read, so it does
+/// not need to be loaded into memory.
+pub fn parse_save(
+    input: impl BufRead,
+    installed: &[ModMetadata],
+) -> Result<Modlist, Box<dyn Error>> {
+    let mut modlist = save::parse(input)?;
+    resolve_installed(&mut modlist.entries, installed);
+    Ok(modlist)
+}
+
+/// Release of a game version such as `1.5.4104 rev435`, e.g. `1.5`.
+pub fn release_of(game_version: &str) -> &str {
+    let version = game_version.split_whitespace().next().unwrap_or_default();
+    match version.match_indices('.').nth(1) {
+        Some((idx, _)) => &version[..idx],
+        None => version,
+    }
+}
+
+/// Strip the `_steam` suffix and add workshop ids and names of installed mods.
+fn resolve_installed(entries: &mut [ModlistEntry], installed: &[ModMetadata]) {
+    // Package ids are case-insensitive in game. Copies with a workshop id are preferred, since the
+    // suffix only marks workshop mods.
+    let mut by_package_id: BTreeMap<String, &ModMetadata> = BTreeMap::new();
+    for metadata in installed {
+        let entry = by_package_id
+            .entry(metadata.package_id.to_lowercase())
+            .or_insert(metadata);
+        if entry.workshop_id.is_none() {
+            *entry = metadata;
+        }
+    }
+
+    for entry in entries {
+        // Lists that only contain identifiers use them as names
+        let unnamed = entry
+            .identifiers
+            .iter()
+            .any(|x| x.to_string() == entry.name);
+        let mut metadata = None;
+        for identifier in &mut entry.identifiers {
+            if let ModIdentifier::PackageId(package_id) = identifier {
+                if let Some(stripped) = package_id.strip_suffix(STEAM_SUFFIX) {
+                    *package_id = stripped.to_string();
+                }
+                metadata = metadata.or(by_package_id.get(&package_id.to_lowercase()));
+            }
+        }
+
+        let Some(metadata) = metadata else {
+            if unnamed && let Some(identifier) = entry.identifiers.first() {
+                entry.name = identifier.to_string();
+            }
+            continue;
+        };
+        if let Some(workshop_id) = metadata.workshop_id
+            && !entry
+                .identifiers
+                .contains(&ModIdentifier::WorkshopId(workshop_id))
+        {
+            entry
+                .identifiers
+                .push(ModIdentifier::WorkshopId(workshop_id));
+        }
+        if unnamed {
+            entry.name = metadata.name.clone();
+        }
+    }
+}
+
+/// Extract the workshop id from a Steam Workshop URL such as
+/// `https://steamcommunity.com/sharedfiles/filedetails/?id=1508850027`.
+pub fn parse_workshop_url(url: &str) -> Option<u64> {
+    let without_scheme = url
+        .trim()
+        .strip_prefix("https://")
+        .or_else(|| url.trim().strip_prefix("http://"))?;
+    let (host, rest) = without_scheme.split_once('/')?;
+    if host != "steamcommunity.com" && host != "www.steamcommunity.com" {
+        return None;
+    }
+
+    let (_, query) = rest.split_once('?')?;
+    query
+        .split(['&', '#'])
+        .find_map(|pair| pair.strip_prefix("id="))
+        .and_then(|id| id.parse().ok())
+}
+
+/// Text of the elements of an XML document with the line each was found on, by element path such
+/// as `ModsConfigData/activeMods/li`. Elements without text or child elements have empty text.
+type XmlValues = BTreeMap<String, Vec<(String, usize)>>;
+
+/// Collect the text of the elements of an XML document. If `stop_after` is the path of an element,
+/// reading stops once it is closed, leaving the rest of the document unread.
+fn xml_values(input: impl BufRead, stop_after: Option<&str>) -> Result<XmlValues, Box<dyn Error>> {
+    let mut reader = Reader::from_reader(LineCounter::new(input));
+    let mut buf = Vec::new();
+    let mut path: Vec<String> = Vec::new();
+    let mut values = XmlValues::new();
+    // Escaped text of the current element, which is split up by references, and the line it ends on
+    let mut text = String::new();
+    let mut text_end_line = 1;
+    // Whether the current element has neither text nor child elements so far
+    let mut empty = false;
+
+    loop {
+        buf.clear();
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| format!("Unable to parse XML at line {}: {e}", reader.get_ref().line))?;
+        let chunk = match &event {
+            Event::Text(x) => Some(x.xml_content()?.into_owned()),
+            Event::GeneralRef(x) => Some(format!("&{};", x.decode()?)),
+            Event::CData(x) => Some(quick_xml::escape::escape(x.decode()?).into_owned()),
+            _ => None,
+        };
+        if let Some(chunk) = chunk {
+            text.push_str(&chunk);
+            text_end_line = reader.get_ref().line;
+            continue;
+        }
+
+        if !text.trim().is_empty() {
+            let unescaped = quick_xml::escape::unescape(&text)
+                .map_err(|e| format!("Invalid escape at line {text_end_line}: {e}"))?;
+            // Count back from the end, skipping leading whitespace which may span lines
+            let line = text_end_line - text.trim_start().matches('\n').count();
+            values
+                .entry(path.join("/"))
+                .or_default()
+                .push((unescaped.trim().to_string(), line));
+            empty = false;
+        }
+        text.clear();
+
+        match event {
+            Event::Start(start) => {
+                path.push(String::from_utf8_lossy(start.name().as_ref()).into_owned());
+                empty = true;
+            }
+            Event::Empty(element) => {
+                path.push(String::from_utf8_lossy(element.name().as_ref()).into_owned());
+                values
+                    .entry(path.join("/"))
+                    .or_default()
+                    .push((String::new(), reader.get_ref().line));
+                path.pop();
+                empty = false;
+            }
+            Event::End(_) => {
+                if empty {
+                    values
+                        .entry(path.join("/"))
+                        .or_default()
+                        .push((String::new(), reader.get_ref().line));
+                }
+                if stop_after.is_some_and(|x| x == path.join("/")) {
+                    break;
+                }
+                path.pop();
+                empty = false;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(values)
+}
+
+/// Tracks the line of the bytes read so far, so positions are known without keeping the input.
+struct LineCounter<R> {
+    inner: R,
+    /// 1-based line of the next byte
+    line: usize,
+}
+
+impl<R> LineCounter<R> {
+    fn new(inner: R) -> Self {
+        Self { inner, line: 1 }
+    }
+}
+
+impl<R: Read> Read for LineCounter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.line += buf[..read].iter().filter(|&&x| x == b'\n').count();
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for LineCounter<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let Ok(buf) = self.inner.fill_buf() {
+            self.line += buf[..amt.min(buf.len())]
+                .iter()
+                .filter(|&&x| x == b'\n')
+                .count();
+        }
+        self.inner.consume(amt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_formats() {
+        for (input, format) in [
+            (
+                include_str!("../../fixtures/modlists/clipboard.txt"),
+                ModlistFormat::Clipboard,
+            ),
+            (
+                include_str!("../../fixtures/mods_config/ModsConfig.xml"),
+                ModlistFormat::ModsConfig,
+            ),
+            (
+                include_str!("../../fixtures/modlists/modlist.rml"),
+                ModlistFormat::Rimpy,
+            ),
+            (
+                include_str!("../../fixtures/modlists/rentry.md"),
+                ModlistFormat::Rentry,
+            ),
+            (
+                include_str!("../../fixtures/saves/Example.rws"),
+                ModlistFormat::Save,
+            ),
+            (
+                include_str!("../../fixtures/modlists/plain.txt"),
+                ModlistFormat::Plain,
+            ),
+        ] {
+            assert_eq!(ModlistFormat::detect(input), format);
+        }
+    }
+
+    #[test]
+    fn byte_order_mark_is_skipped() {
+        let input = format!(
+            "\u{feff}{}",
+            include_str!("../../fixtures/modlists/modlist.rml")
+        );
+        let modlist = parse(&input, None, &[]).unwrap();
+        assert_eq!(modlist.format, ModlistFormat::Rimpy);
+        assert_eq!(modlist.entries.len(), 3);
+    }
+
+    #[test]
+    fn parses_workshop_urls() {
+        assert_eq!(
+            parse_workshop_url("https://steamcommunity.com/sharedfiles/filedetails/?id=1508850027"),
+            Some(1508850027)
+        );
+        assert_eq!(
+            parse_workshop_url(
+                "http://www.steamcommunity.com/workshop/filedetails/?searchtext=&id=1508850027#x"
+            ),
+            Some(1508850027)
+        );
+        assert_eq!(
+            parse_workshop_url("https://example.com/sharedfiles/filedetails/?id=1508850027"),
+            None
+        );
+        assert_eq!(parse_workshop_url("Not found"), None);
+    }
+
+    #[test]
+    fn finds_releases() {
+        assert_eq!(release_of("1.5.4104 rev435"), "1.5");
+        assert_eq!(release_of("1.6"), "1.6");
+    }
+}
@@ -0,0 +1,149 @@
+use std::error::Error;
+
+use crate::{
+    modlist::{Modlist, ModlistFormat, XmlValues, plain::is_package_id, xml_values},
+    records::types::ModIdentifier,
+};
+
+/// Parse the game's `ModsConfig.xml`, or a RimSort XML list export which shares its format. Active
+/// expansions are listed like mods.
+pub fn parse(input: &str) -> Result<Modlist, Box<dyn Error>> {
+    let mut values = xml_values(input.as_bytes(), None)?;
+    let mut modlist = Modlist::new(ModlistFormat::ModsConfig);
+
+    modlist.game_version = values
+        .remove("ModsConfigData/version")
+        .and_then(|x| x.into_iter().next())
+        .map(|(version, _)| version)
+        .filter(|x| !x.is_empty());
+    for (package_id, line) in package_ids(&mut modlist, &mut values, "activeMods") {
+        let identifiers = vec![ModIdentifier::PackageId(package_id.clone())];
+        modlist.push(line, package_id, identifiers);
+    }
+    modlist.known_expansions = package_ids(&mut modlist, &mut values, "knownExpansions")
+        .into_iter()
+        .map(|(package_id, _)| package_id)
+        .collect();
+
+    Ok(modlist)
+}
+
+/// Package ids listed in the `<li>` elements of `list`. Empty elements and those not containing a
+/// package id are skipped with a warning.
+fn package_ids(modlist: &mut Modlist, values: &mut XmlValues, list: &str) -> Vec<(String, usize)> {
+    let path = format!("ModsConfigData/{list}/li");
+    // Elements nested in an entry are not part of the format
+    let nested: Vec<String> = values
+        .keys()
+        .filter(|x| x.starts_with(&format!("{path}/")))
+        .cloned()
+        .collect();
+    let mut malformed: Vec<(String, usize)> = nested
+        .iter()
+        .filter_map(|x| values.remove(x))
+        .flatten()
+        .collect();
+
+    let mut package_ids = Vec::new();
+    for (value, line) in values.remove(&path).unwrap_or_default() {
+        if value.is_empty() {
+            modlist.warn(line, "<li></li>", format!("Empty entry in {list}"));
+        } else if is_package_id(&value) {
+            package_ids.push((value, line));
+        } else {
+            malformed.push((value, line));
+        }
+    }
+    malformed.sort_by_key(|(_, line)| *line);
+    for (value, line) in malformed {
+        modlist.warn(line, &value, format!("Expected a package id in {list}"));
+    }
+
+    package_ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_mods_config() {
+        let modlist = parse(include_str!("../../fixtures/mods_config/ModsConfig.xml")).unwrap();
+        assert_eq!(modlist.game_version.as_deref(), Some("1.6.4566 rev602"));
+        assert_eq!(
+            modlist.known_expansions,
+            [
+                "ludeon.rimworld.royalty",
+                "ludeon.rimworld.ideology",
+                "ludeon.rimworld.biotech"
+            ]
+        );
+        assert!(modlist.warnings.is_empty());
+
+        let active: Vec<_> = modlist
+            .entries
+            .iter()
+            .map(|x| {
+                let position = x.position.unwrap();
+                (x.name.as_str(), position.line, position.index)
+            })
+            .collect();
+        assert_eq!(
+            active,
+            [
+                ("brrainz.harmony_steam", 5, 0),
+                ("ludeon.rimworld", 6, 1),
+                ("ludeon.rimworld.royalty", 7, 2),
+                ("ludeon.rimworld.biotech", 8, 3),
+                ("example.outdated_steam", 9, 4),
+                ("oblitus.animalslogic", 10, 5),
+            ]
+        );
+        assert_eq!(
+            modlist.entries[0].identifiers,
+            [ModIdentifier::PackageId("brrainz.harmony_steam".into())]
+        );
+    }
+
+    #[test]
+    fn empty_and_malformed_entries_are_reported() {
+        let modlist = parse(
+            "<ModsConfigData>
+                <activeMods>
+                    <li>brrainz.harmony</li>
+                    <li></li>
+                    <li/>
+                    <li>Not a package id</li>
+                    <li><packageId>example.nested</packageId></li>
+                    <li>ludeon.rimworld</li>
+                </activeMods>
+                <knownExpansions><li>  </li></knownExpansions>
+            </ModsConfigData>",
+        )
+        .unwrap();
+        let warnings: Vec<_> = modlist
+            .warnings
+            .iter()
+            .map(|x| (x.line, x.message.as_str()))
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                (4, "Empty entry in activeMods"),
+                (5, "Empty entry in activeMods"),
+                (6, "Expected a package id in activeMods"),
+                (7, "Expected a package id in activeMods"),
+                (10, "Empty entry in knownExpansions"),
+            ]
+        );
+
+        // Skipped entries do not take up a position
+        let active: Vec<_> = modlist
+            .entries
+            .iter()
+            .map(|x| (x.name.as_str(), x.position.unwrap().index))
+            .collect();
+        assert_eq!(active, [("brrainz.harmony", 0), ("ludeon.rimworld", 1)]);
+        assert!(modlist.known_expansions.is_empty());
+    }
+}
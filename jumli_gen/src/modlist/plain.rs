@@ -0,0 +1,86 @@
+use crate::{
+    modlist::{Modlist, ModlistFormat, parse_workshop_url},
+    records::types::ModIdentifier,
+};
+
+/// Parse a list with one package id, workshop id or workshop URL per line. Empty lines and lines
+/// starting with `#` or `//` are skipped.
+pub fn parse(input: &str) -> Modlist {
+    let mut modlist = Modlist::new(ModlistFormat::Plain);
+    for (idx, line) in input.lines().enumerate() {
+        let item = line.trim();
+        if item.is_empty() || item.starts_with('#') || item.starts_with("//") {
+            continue;
+        }
+
+        let identifier = if let Some(workshop_id) = parse_workshop_url(item) {
+            ModIdentifier::WorkshopId(workshop_id)
+        } else if let Ok(workshop_id) = item.parse() {
+            ModIdentifier::WorkshopId(workshop_id)
+        } else if is_package_id(item) {
+            ModIdentifier::PackageId(item.to_string())
+        } else {
+            modlist.warn(
+                idx + 1,
+                line,
+                "Expected a package id, workshop id or workshop URL",
+            );
+            continue;
+        };
+        modlist.push(idx + 1, identifier.to_string(), vec![identifier]);
+    }
+
+    modlist
+}
+
+/// Package ids look like `author.mod`, consisting of letters, digits, dots and underscores.
+pub(super) fn is_package_id(item: &str) -> bool {
+    item.contains('.')
+        && !item.starts_with('.')
+        && !item.ends_with('.')
+        && item
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_identifiers() {
+        let modlist = parse(include_str!("../../fixtures/modlists/plain.txt"));
+
+        let identifiers: Vec<_> = modlist
+            .entries
+            .iter()
+            .flat_map(|x| x.identifiers.clone())
+            .collect();
+        assert_eq!(
+            identifiers,
+            [
+                ModIdentifier::PackageId("brrainz.harmony".into()),
+                ModIdentifier::WorkshopId(1000000001),
+                ModIdentifier::WorkshopId(1541438694),
+            ]
+        );
+        // Identifiers are used as names until resolved
+        assert_eq!(modlist.entries[1].name, "1000000001");
+        let position = modlist.entries[2].position.unwrap();
+        assert_eq!((position.line, position.index), (5, 2));
+
+        assert_eq!(modlist.warnings.len(), 1);
+        assert_eq!(modlist.warnings[0].line, 6);
+        assert_eq!(modlist.warnings[0].content, "not a mod");
+    }
+
+    #[test]
+    fn recognizes_package_ids() {
+        assert!(is_package_id("brrainz.harmony"));
+        assert!(is_package_id("Mlie.Some_Mod2"));
+        assert!(!is_package_id("harmony"));
+        assert!(!is_package_id(".harmony"));
+        assert!(!is_package_id("brrainz.harmony."));
+        assert!(!is_package_id("brrainz harmony.x"));
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{
+    modlist::{Modlist, ModlistFormat, parse_workshop_url},
+    records::types::ModIdentifier,
+};
+
+/// Prefix of local mods, which are highlighted as warnings
+const LOCAL_PREFIX: &str = "!!! warning";
+
+/// Whether a line looks like a mod of a Rentry modlist.
+pub fn is_entry(line: &str) -> bool {
+    parse_line(line).is_some()
+}
+
+/// Parse the markup of a Rentry modlist, as exported by RimSort and RimPy.
+///
+/// Mods are numbered, workshop mods look like
+/// `1. ![](image) [name](https://steamcommunity.com/sharedfiles/filedetails/?id=1 packageid: id)` and
+/// local ones like `!!! warning 2. [name] {packageid: id}`. Lines before the first mod are skipped.
+pub fn parse(input: &str) -> Modlist {
+    let mut modlist = Modlist::new(ModlistFormat::Rentry);
+    let mut started = false;
+    for (idx, line) in input.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match parse_line(line) {
+            Some((name, identifiers)) => {
+                started = true;
+                modlist.push(idx + 1, name, identifiers);
+            }
+            None if started => modlist.warn(
+                idx + 1,
+                line,
+                "Expected a numbered workshop link or local mod with a package id",
+            ),
+            // RimSort records the game version in the header
+            None => {
+                if let Some((_, version)) = line.split_once("game version:") {
+                    modlist.game_version = Some(version.trim().to_string());
+                }
+            }
+        }
+    }
+
+    modlist
+}
+
+fn parse_line(line: &str) -> Option<(String, Vec<ModIdentifier>)> {
+    let line = line.trim();
+    match line.strip_prefix(LOCAL_PREFIX) {
+        Some(rest) => parse_local(strip_number(rest.trim_start())?),
+        None => parse_workshop(strip_number(line)?),
+    }
+}
+
+/// Strip the `1. ` numbering of a list item.
+fn strip_number(line: &str) -> Option<&str> {
+    let (number, rest) = line.split_once(". ")?;
+    number.parse::<usize>().ok()?;
+    Some(rest)
+}
+
+fn parse_workshop(item: &str) -> Option<(String, Vec<ModIdentifier>)> {
+    let link_start = item.rfind("](")?;
+    let name = &item[item[..link_start].rfind('[')? + 1..link_start];
+    let target = item[link_start + 2..].trim_end().strip_suffix(')')?;
+    let (url, package_id) = split_package_id(target)?;
+
+    Some((
+        name.trim().to_string(),
+        vec![
+            ModIdentifier::PackageId(package_id),
+            ModIdentifier::WorkshopId(parse_workshop_url(url)?),
+        ],
+    ))
+}
+
+fn parse_local(item: &str) -> Option<(String, Vec<ModIdentifier>)> {
+    let (name, rest) = item.split_once('{')?;
+    let (_, package_id) = split_package_id(rest.trim_end().strip_suffix('}')?)?;
+    let name = name.trim();
+    let name = name
+        .strip_prefix('[')
+        .and_then(|x| x.strip_suffix(']'))
+        .unwrap_or(name);
+
+    Some((
+        name.trim().to_string(),
+        vec![ModIdentifier::PackageId(package_id)],
+    ))
+}
+
+/// Split `<before> packageid: <id>`, where the label's case varies between exporters.
+fn split_package_id(text: &str) -> Option<(&str, String)> {
+    const LABEL: &str = "packageid:";
+    // Lowercasing can change the length of non-ASCII text, so the label is searched in place
+    let label = text
+        .as_bytes()
+        .windows(LABEL.len())
+        .position(|x| x.eq_ignore_ascii_case(LABEL.as_bytes()))?;
+    let package_id = text[label + LABEL.len()..].trim();
+    if package_id.is_empty() {
+        return None;
+    }
+    Some((text[..label].trim(), package_id.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rimsort_export() {
+        let modlist = parse(include_str!("../../fixtures/modlists/rentry.md"));
+        assert_eq!(modlist.game_version.as_deref(), Some("1.5.4104 rev435"));
+
+        let names: Vec<_> = modlist.entries.iter().map(|x| x.name.as_str()).collect();
+        assert_eq!(names, ["Harmony", "Outdated Example", "Local Example"]);
+        assert_eq!(
+            modlist.entries[0].identifiers,
+            [
+                ModIdentifier::PackageId("brrainz.harmony".into()),
+                ModIdentifier::WorkshopId(2009463077)
+            ]
+        );
+        // Local mods have no workshop id
+        assert_eq!(
+            modlist.entries[2].identifiers,
+            [ModIdentifier::PackageId("example.local".into())]
+        );
+        let position = modlist.entries[2].position.unwrap();
+        assert_eq!((position.line, position.index), (8, 2));
+
+        assert_eq!(modlist.warnings.len(), 1);
+        assert_eq!(modlist.warnings[0].line, 9);
+        assert_eq!(modlist.warnings[0].content, "4. this line is broken");
+    }
+
+    #[test]
+    fn package_id_label_ignores_case() {
+        assert_eq!(
+            split_package_id("url PackageId: author.mod"),
+            Some(("url", "author.mod".into()))
+        );
+        // Lowercases to more bytes than it takes up
+        assert_eq!(
+            split_package_id("İstanbul packageId: author.mod"),
+            Some(("İstanbul", "author.mod".into()))
+        );
+        assert_eq!(split_package_id("url packageid: "), None);
+        assert_eq!(split_package_id("İ"), None);
+    }
+}
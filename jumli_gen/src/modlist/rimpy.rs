@@ -0,0 +1,141 @@
+use std::{collections::HashMap, error::Error};
+
+use crate::{
+    modlist::{Modlist, ModlistFormat, XmlValues, xml_values},
+    records::types::ModIdentifier,
+};
+
+/// Parse a saved mod list (`.rml`), as written by RimPy and the game's mod manager.
+///
+/// The list contains the package ids and names of the mods in `modList`, and repeats them with
+/// their workshop ids (0 for local mods) in `meta`.
+pub fn parse(input: &str) -> Result<Modlist, Box<dyn Error>> {
+    let values = xml_values(input.as_bytes(), None)?;
+    Ok(from_values(ModlistFormat::Rimpy, values, "savedModList"))
+}
+
+/// Build a modlist from the `meta` and optional `modList` elements below `root`, which saves share
+/// with saved mod lists.
+pub(super) fn from_values(format: ModlistFormat, mut values: XmlValues, root: &str) -> Modlist {
+    let mut modlist = Modlist::new(format);
+    let mut take = |path: &str| {
+        let mut values = values.remove(&format!("{root}/{path}")).unwrap_or_default();
+        values.retain(|(value, _)| !value.is_empty());
+        values
+    };
+
+    modlist.game_version = take("meta/gameVersion")
+        .into_iter()
+        .next()
+        .map(|(version, _)| version);
+
+    let meta_ids = take("meta/modIds/li");
+    let meta_steam_ids = take("meta/modSteamIds/li");
+    let meta_names = take("meta/modNames/li");
+    let (ids, names) = match take("modList/ids/li") {
+        ids if ids.is_empty() => (meta_ids.clone(), meta_names),
+        ids => (ids, take("modList/names/li")),
+    };
+
+    if meta_steam_ids.len() != meta_ids.len() {
+        modlist.warn(
+            meta_steam_ids.first().map_or(1, |(_, line)| *line),
+            "modSteamIds",
+            format!(
+                "Found {} workshop ids for {} mods, workshop ids are ignored",
+                meta_steam_ids.len(),
+                meta_ids.len()
+            ),
+        );
+    }
+    let steam_ids: HashMap<&str, u64> = match meta_steam_ids.len() == meta_ids.len() {
+        true => meta_ids
+            .iter()
+            .zip(&meta_steam_ids)
+            .filter_map(|((package_id, _), (steam_id, _))| {
+                Some((
+                    package_id.as_str(),
+                    steam_id.parse().ok().filter(|x| *x != 0)?,
+                ))
+            })
+            .collect(),
+        false => HashMap::new(),
+    };
+
+    if names.len() != ids.len() {
+        modlist.warn(
+            ids.first().map_or(1, |(_, line)| *line),
+            "names",
+            format!(
+                "Found {} names for {} mods, package ids are used as names",
+                names.len(),
+                ids.len()
+            ),
+        );
+    }
+
+    for (idx, (package_id, line)) in ids.iter().enumerate() {
+        let mut identifiers = vec![ModIdentifier::PackageId(package_id.clone())];
+        identifiers.extend(
+            steam_ids
+                .get(package_id.as_str())
+                .map(|x| ModIdentifier::WorkshopId(*x)),
+        );
+        let name = match names.len() == ids.len() {
+            true => names[idx].0.clone(),
+            false => package_id.clone(),
+        };
+        modlist.push(*line, name, identifiers);
+    }
+
+    modlist
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_saved_mod_list() {
+        let modlist = parse(include_str!("../../fixtures/modlists/modlist.rml")).unwrap();
+        assert_eq!(modlist.game_version.as_deref(), Some("1.5.4104 rev435"));
+        assert!(modlist.warnings.is_empty());
+
+        let names: Vec<_> = modlist.entries.iter().map(|x| x.name.as_str()).collect();
+        assert_eq!(names, ["Harmony", "Core", "Outdated & Example"]);
+        assert_eq!(
+            modlist.entries[0].identifiers,
+            [
+                ModIdentifier::PackageId("brrainz.harmony".into()),
+                ModIdentifier::WorkshopId(2009463077)
+            ]
+        );
+        // Local mods have a workshop id of 0
+        assert_eq!(
+            modlist.entries[1].identifiers,
+            [ModIdentifier::PackageId("ludeon.rimworld".into())]
+        );
+        // Positions are taken from modList rather than meta
+        let position = modlist.entries[2].position.unwrap();
+        assert_eq!((position.line, position.index), (25, 2));
+    }
+
+    #[test]
+    fn mismatched_lists_are_reported() {
+        let modlist = parse(
+            "<savedModList><meta>
+                <modIds><li>brrainz.harmony</li><li>ludeon.rimworld</li></modIds>
+                <modSteamIds><li>2009463077</li></modSteamIds>
+                <modNames><li>Harmony</li></modNames>
+            </meta></savedModList>",
+        )
+        .unwrap();
+        assert_eq!(modlist.warnings.len(), 2);
+        // Without names or matching workshop ids, only package ids are known
+        assert_eq!(modlist.entries[0].name, "brrainz.harmony");
+        assert_eq!(
+            modlist.entries[0].identifiers,
+            [ModIdentifier::PackageId("brrainz.harmony".into())]
+        );
+    }
+}
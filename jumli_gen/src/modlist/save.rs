@@ -0,0 +1,55 @@
+use std::{error::Error, io::BufRead};
+
+use crate::modlist::{Modlist, ModlistFormat, rimpy, xml_values};
+
+/// Read the mods of a RimWorld save (`.rws`) from its `meta` section, which lists the active mods
+/// like a saved mod list. Reading stops after the section, so the rest of the save is never loaded.
+pub fn parse(input: impl BufRead) -> Result<Modlist, Box<dyn Error>> {
+    let values = xml_values(input, Some("savegame/meta"))?;
+    Ok(rimpy::from_values(ModlistFormat::Save, values, "savegame"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::records::types::ModIdentifier;
+
+    /// The fixture ends in the middle of the game, as if it was cut off
+    const SAVE: &str = include_str!("../../fixtures/saves/Example.rws");
+
+    #[test]
+    fn reads_only_the_meta_section() {
+        let modlist = parse(SAVE.as_bytes()).unwrap();
+        assert_eq!(modlist.format, ModlistFormat::Save);
+        assert_eq!(modlist.game_version.as_deref(), Some("1.5.4104 rev435"));
+        assert!(modlist.warnings.is_empty());
+
+        let names: Vec<_> = modlist.entries.iter().map(|x| x.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Harmony",
+                "Core",
+                "Royalty",
+                "Outdated Example",
+                "Animals Logic"
+            ]
+        );
+        assert_eq!(
+            modlist.entries[4].identifiers,
+            [
+                ModIdentifier::PackageId("oblitus.animalslogic".into()),
+                ModIdentifier::WorkshopId(1541438694)
+            ]
+        );
+        let position = modlist.entries[0].position.unwrap();
+        assert_eq!((position.line, position.index), (6, 0));
+    }
+
+    #[test]
+    fn stops_after_the_meta_section() {
+        let values = xml_values(SAVE.as_bytes(), Some("savegame/meta")).unwrap();
+        assert!(values.contains_key("savegame/meta/loadID"));
+        assert!(!values.contains_key("savegame/game/currentMapIndex"));
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    collections::HashSet,
+    io,
+    path::{Path, PathBuf},
+};
+
+use tracing::debug;
+
+/// Writes the generated site below an output directory.
+///
+/// In incremental mode, existing directories are reused and files are only rewritten if their content
+/// changed, which keeps modification times stable for rsync-style deploys.
+pub struct OutputDir {
+    root: PathBuf,
+    incremental: bool,
+    /// Every file written (or found unchanged) during this build
+    written: HashSet<PathBuf>,
+    unchanged_count: usize,
+}
+
+impl OutputDir {
+    pub fn new(root: PathBuf, incremental: bool) -> Self {
+        Self {
+            root,
+            incremental,
+            written: HashSet::new(),
+            unchanged_count: 0,
+        }
+    }
+
+    /// Create a directory. Fails if it already exists, unless building incrementally.
+    pub fn create_dir(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = self.root.join(path);
+        if self.incremental {
+            std::fs::create_dir_all(path)
+        } else {
+            std::fs::create_dir(&path).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("Unable to create {path:?}: {e}. Use --incremental to reuse existing output."),
+                )
+            })
+        }
+    }
+
+    pub fn write(&mut self, path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+        let path = self.root.join(path);
+        let contents = contents.as_ref();
+        self.written.insert(path.clone());
+
+        if self.incremental && std::fs::read(&path).is_ok_and(|existing| existing == contents) {
+            self.unchanged_count += 1;
+            return Ok(());
+        }
+
+        std::fs::write(path, contents)
+    }
+
+    pub fn copy(&mut self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+        let contents = std::fs::read(from)?;
+        self.write(to, contents)
+    }
+
+    /// Delete files below `subdir` that were not written during this build, as well as directories
+    /// left empty by doing so. Returns the number of deleted files.
+    pub fn remove_stale(&self, subdir: impl AsRef<Path>) -> io::Result<usize> {
+        fn recurse(output: &OutputDir, dir: &Path) -> io::Result<usize> {
+            let mut removed = 0;
+            for entry in std::fs::read_dir(dir)? {
+                let entry = entry?;
+                let path = entry.path();
+                if entry.file_type()?.is_dir() {
+                    removed += recurse(output, &path)?;
+                    if std::fs::read_dir(&path)?.next().is_none() {
+                        std::fs::remove_dir(&path)?;
+                    }
+                } else if !output.written.contains(&path) {
+                    debug!("Removing stale file {path:?}.");
+                    std::fs::remove_file(&path)?;
+                    removed += 1;
+                }
+            }
+            Ok(removed)
+        }
+
+        let dir = self.root.join(subdir);
+        if !dir.is_dir() {
+            return Ok(0);
+        }
+        recurse(self, &dir)
+    }
+
+    pub fn written_count(&self) -> usize {
+        self.written.len()
+    }
+
+    pub fn unchanged_count(&self) -> usize {
+        self.unchanged_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{File, FileTimes},
+        time::{Duration, SystemTime},
+    };
+
+    use super::*;
+
+    /// A modification time no write during the test can have.
+    const OLD: Duration = Duration::from_secs(1_000_000);
+
+    fn age(path: &Path) {
+        let times = FileTimes::new().set_modified(SystemTime::UNIX_EPOCH + OLD);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_times(times)
+            .unwrap();
+    }
+
+    fn modified(path: &Path) -> SystemTime {
+        std::fs::metadata(path).unwrap().modified().unwrap()
+    }
+
+    #[test]
+    fn rewrites_only_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut output = OutputDir::new(dir.path().into(), true);
+        output.write("same.html", "same").unwrap();
+        output.write("changed.html", "old").unwrap();
+        let (same, changed) = (
+            dir.path().join("same.html"),
+            dir.path().join("changed.html"),
+        );
+        age(&same);
+        age(&changed);
+
+        let mut output = OutputDir::new(dir.path().into(), true);
+        output.write("same.html", "same").unwrap();
+        output.write("changed.html", "new").unwrap();
+        assert_eq!(modified(&same), SystemTime::UNIX_EPOCH + OLD);
+        assert_ne!(modified(&changed), SystemTime::UNIX_EPOCH + OLD);
+        assert_eq!(std::fs::read_to_string(&changed).unwrap(), "new");
+        assert_eq!(output.written_count(), 2);
+        assert_eq!(output.unchanged_count(), 1);
+    }
+
+    #[test]
+    fn removes_stale_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut output = OutputDir::new(dir.path().into(), true);
+        output.create_dir("mods/old").unwrap();
+        output.write("mods/kept.html", "").unwrap();
+        output.write("mods/stale.html", "").unwrap();
+        output.write("mods/old/stale.html", "").unwrap();
+        output.write("outside.html", "").unwrap();
+
+        let mut output = OutputDir::new(dir.path().into(), true);
+        output.create_dir("mods").unwrap();
+        output.write("mods/kept.html", "").unwrap();
+        assert_eq!(output.remove_stale("mods").unwrap(), 2);
+
+        assert!(dir.path().join("mods/kept.html").exists());
+        assert!(!dir.path().join("mods/stale.html").exists());
+        // Directories left empty are removed as well
+        assert!(!dir.path().join("mods/old").exists());
+        // Files outside the directory are left alone
+        assert!(dir.path().join("outside.html").exists());
+        assert_eq!(output.remove_stale("missing").unwrap(), 0);
+    }
+
+    #[test]
+    fn fresh_builds_need_an_empty_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = OutputDir::new(dir.path().into(), false);
+        output.create_dir("mods").unwrap();
+        assert!(output.create_dir("mods").is_err());
+    }
+}
@@ -0,0 +1,411 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use serde_json::json;
+
+use crate::{
+    records::{
+        stable_id,
+        types::{Alternative, AlternativeChain, Backlink, ModIdentifier, ModRecord, Notice},
+    },
+    sources::{DiagnosticEntry, Diagnostics},
+};
+
+/// The first active alternative a record recommends. Records recommending several alternatives
+/// are followed along the first one.
+fn recommended(record: &ModRecord) -> Option<Alternative> {
+    record
+        .notices
+        .iter()
+        .find_map(|notice| match &notice.notice {
+            Notice::UseAlternative(name, workshop_id, _) if !notice.historical => {
+                Some(Alternative {
+                    name: name.clone(),
+                    workshop_id: *workshop_id,
+                })
+            }
+            _ => None,
+        })
+}
+
+/// Empty records for alternatives recommended by workshop id that no record lists.
+pub(super) fn alternative_stubs(records: &[ModRecord]) -> Vec<ModRecord> {
+    let known: HashSet<&ModIdentifier> = records.iter().flat_map(|x| &x.identifiers).collect();
+    let missing: BTreeSet<ModIdentifier> = records
+        .iter()
+        .flat_map(|x| &x.notices)
+        .filter(|x| !x.historical)
+        .filter_map(|x| match x.notice {
+            Notice::UseAlternative(_, Some(workshop_id), _) => {
+                Some(ModIdentifier::WorkshopId(workshop_id))
+            }
+            _ => None,
+        })
+        .filter(|x| !x.is_invalid() && !known.contains(x))
+        .collect();
+
+    missing
+        .into_iter()
+        .map(|identifier| {
+            let identifiers = BTreeSet::from([identifier]);
+            ModRecord {
+                id: stable_id(&identifiers),
+                notices: Vec::new(),
+                identifiers: identifiers.into_iter().collect(),
+                recommended_instead_of: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+/// Record the mods recommending each mod as their alternative, in the order of their records.
+pub(super) fn link_alternatives(records: &mut [ModRecord], indices: &HashMap<String, usize>) {
+    let mut backlinks: BTreeMap<usize, Vec<Backlink>> = BTreeMap::new();
+    for (record_idx, record) in records.iter().enumerate() {
+        for notice in &record.notices {
+            let Notice::UseAlternative(_, Some(workshop_id), _) = &notice.notice else {
+                continue;
+            };
+            let identifier = ModIdentifier::WorkshopId(*workshop_id);
+            if notice.historical || identifier.is_invalid() {
+                continue;
+            }
+            let Some(&idx) = indices.get(&identifier.to_string()) else {
+                continue;
+            };
+            if idx == record_idx {
+                continue;
+            }
+
+            let links = backlinks.entry(idx).or_default();
+            // A mod recommending the same alternative in several sources is listed once
+            if links.last().is_none_or(|x| x.id != record.id) {
+                links.push(Backlink {
+                    id: record.id.clone(),
+                    identifiers: record.identifiers.clone(),
+                    sources: Vec::new(),
+                });
+            }
+            let last = links.len() - 1;
+            let sources = &mut links[last].sources;
+            for source in std::iter::once(&notice.source)
+                .chain(notice.corroborations.iter().map(|x| &x.source))
+            {
+                let name = source.to_string();
+                if !sources.iter().any(|x| x.to_string() == name) {
+                    sources.push(source.clone());
+                }
+            }
+        }
+    }
+
+    for (idx, links) in backlinks {
+        records[idx].recommended_instead_of = links;
+    }
+}
+
+/// Follow the alternatives recommended by `UseAlternative` notices through the consolidated
+/// records, attaching the chain to notices whose alternative is itself superseded. Chains longer
+/// than one step, cycles and alternatives with concerns of their own are listed in the returned
+/// diagnostics.
+pub(super) fn check_alternatives(
+    records: &mut [ModRecord],
+    indices: &HashMap<String, usize>,
+) -> Diagnostics {
+    let lookup = |workshop_id: Option<u64>| {
+        workshop_id
+            .map(ModIdentifier::WorkshopId)
+            .filter(|x| !x.is_invalid())
+            .and_then(|x| indices.get(&x.to_string()).copied())
+    };
+
+    let mut chains = Vec::new();
+    let mut conflicts = Vec::new();
+    for (record_idx, record) in records.iter().enumerate() {
+        for (notice_idx, notice) in record.notices.iter().enumerate() {
+            let Notice::UseAlternative(name, workshop_id, _) = &notice.notice else {
+                continue;
+            };
+            if notice.historical {
+                continue;
+            }
+
+            if let Some(idx) = lookup(*workshop_id)
+                && idx != record_idx
+            {
+                let concerns: Vec<_> = records[idx]
+                    .notices
+                    .iter()
+                    .filter(|x| x.is_concern())
+                    .map(|x| format!("{} ({})", x.notice.title(), x.source))
+                    .collect();
+                if !concerns.is_empty() {
+                    conflicts.push(DiagnosticEntry {
+                        kind: "alternative_has_concerns".into(),
+                        location: format!("{}.notices[{notice_idx}]", record.id),
+                        message: format!(
+                            "Recommends {name}, which has notices of its own: {}",
+                            concerns.join(", ")
+                        ),
+                        value: Some(json!(workshop_id)),
+                    });
+                }
+            }
+
+            let mut visited = HashSet::from([record_idx]);
+            let mut steps = Vec::new();
+            let mut cycle = false;
+            let mut current = lookup(*workshop_id);
+            while let Some(idx) = current {
+                if !visited.insert(idx) {
+                    cycle = true;
+                    break;
+                }
+                let Some(next) = recommended(&records[idx]) else {
+                    break;
+                };
+                current = lookup(next.workshop_id);
+                steps.push(next);
+            }
+
+            if !steps.is_empty() || cycle {
+                chains.push((
+                    record_idx,
+                    notice_idx,
+                    name.clone(),
+                    *workshop_id,
+                    AlternativeChain { steps, cycle },
+                ));
+            }
+        }
+    }
+
+    let mut diagnostics = Diagnostics::new();
+    let cycles_count = chains.iter().filter(|(.., chain)| chain.cycle).count();
+    diagnostics.add_property("chains_count", (chains.len() - cycles_count).to_string());
+    diagnostics.add_property("cycles_count", cycles_count.to_string());
+    diagnostics.add_property("conflicts_count", conflicts.len().to_string());
+
+    for (record_idx, notice_idx, name, workshop_id, chain) in chains {
+        let record = &mut records[record_idx];
+        let path: Vec<&str> = std::iter::once(name.as_str())
+            .chain(chain.steps.iter().map(|x| x.name.as_str()))
+            .collect();
+        let workshop_ids: Vec<_> = std::iter::once(workshop_id)
+            .chain(chain.steps.iter().map(|x| x.workshop_id))
+            .collect();
+        let (kind, message) = if chain.cycle {
+            (
+                "alternative_cycle",
+                format!("Recommendations lead in a circle: {}", path.join(" -> ")),
+            )
+        } else {
+            (
+                "alternative_chain",
+                format!(
+                    "Recommended alternative is superseded: {}",
+                    path.join(" -> ")
+                ),
+            )
+        };
+        diagnostics.add_entry(DiagnosticEntry {
+            kind: kind.into(),
+            location: format!("{}.notices[{notice_idx}]", record.id),
+            message,
+            value: Some(json!(workshop_ids)),
+        });
+        record.notices[notice_idx].alternative_chain = Some(chain);
+    }
+    for entry in conflicts {
+        diagnostics.add_entry(entry);
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::records::types::{Certainty, NoticeRecord, Source};
+
+    fn notice(notice: Notice) -> NoticeRecord {
+        NoticeRecord {
+            date: None,
+            notice,
+            certainty: Certainty::Inapplicable,
+            source: Source::UseThisInsteadDatabase,
+            context_url: None,
+            historical: false,
+            alternative_chain: None,
+            corroborations: Vec::new(),
+        }
+    }
+
+    /// Record of the mod with `workshop_id`, recommending the mod with `alternative`.
+    fn record(workshop_id: u64, alternative: Option<u64>) -> ModRecord {
+        ModRecord {
+            id: workshop_id.to_string(),
+            notices: alternative
+                .map(|x| notice(Notice::UseAlternative(format!("Mod {x}"), Some(x), None)))
+                .into_iter()
+                .collect(),
+            identifiers: vec![ModIdentifier::WorkshopId(workshop_id)],
+            recommended_instead_of: Vec::new(),
+        }
+    }
+
+    fn indices(records: &[ModRecord]) -> HashMap<String, usize> {
+        records
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, x)| x.identifiers.iter().map(move |y| (y.to_string(), idx)))
+            .collect()
+    }
+
+    fn check(records: &mut [ModRecord]) -> Diagnostics {
+        let indices = indices(records);
+        check_alternatives(records, &indices)
+    }
+
+    fn property<'a>(diagnostics: &'a Diagnostics, key: &str) -> Option<&'a str> {
+        diagnostics
+            .get_properties()?
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn chain(record: &ModRecord) -> Option<(Vec<&str>, bool)> {
+        let chain = record.notices[0].alternative_chain.as_ref()?;
+        let steps = chain.steps.iter().map(|x| x.name.as_str()).collect();
+        Some((steps, chain.cycle))
+    }
+
+    #[test]
+    fn follows_chains() {
+        // 1 -> 2 -> 3, where 3 has notices of its own
+        let mut records = vec![record(1, Some(2)), record(2, Some(3)), record(3, None)];
+        records[2]
+            .notices
+            .push(notice(Notice::BadPerformance(None)));
+        let diagnostics = check(&mut records);
+
+        assert_eq!(chain(&records[0]), Some((vec!["Mod 3"], false)));
+        // The direct alternative is not superseded
+        assert_eq!(chain(&records[1]), None);
+        assert_eq!(property(&diagnostics, "chains_count"), Some("1"));
+        assert_eq!(property(&diagnostics, "cycles_count"), Some("0"));
+        assert_eq!(property(&diagnostics, "conflicts_count"), Some("1"));
+
+        let entries = diagnostics.get_entries().unwrap();
+        assert_eq!(entries[0].kind, "alternative_chain");
+        assert_eq!(entries[0].location, "1.notices[0]");
+        assert_eq!(entries[0].value, Some(json!([2, 3])));
+        assert_eq!(entries[1].kind, "alternative_has_concerns");
+        assert_eq!(entries[1].location, "2.notices[0]");
+    }
+
+    #[test]
+    fn detects_cycles() {
+        // 1 -> 2 -> 1
+        let mut records = vec![record(1, Some(2)), record(2, Some(1))];
+        let diagnostics = check(&mut records);
+
+        assert_eq!(chain(&records[0]), Some((vec!["Mod 1"], true)));
+        assert_eq!(chain(&records[1]), Some((vec!["Mod 2"], true)));
+        assert_eq!(property(&diagnostics, "chains_count"), Some("0"));
+        assert_eq!(property(&diagnostics, "cycles_count"), Some("2"));
+        assert!(
+            diagnostics
+                .get_entries()
+                .unwrap()
+                .iter()
+                .all(|x| x.kind == "alternative_cycle")
+        );
+    }
+
+    #[test]
+    fn detects_self_recommendations() {
+        // A mod consolidated with its recommended alternative, e.g. through an alias
+        let mut records = vec![record(1, Some(1))];
+        records[0]
+            .notices
+            .push(notice(Notice::BadPerformance(None)));
+        let diagnostics = check(&mut records);
+
+        assert_eq!(chain(&records[0]), Some((vec![], true)));
+        assert_eq!(property(&diagnostics, "cycles_count"), Some("1"));
+        // Its own notices are not reported as the alternative's
+        assert_eq!(property(&diagnostics, "conflicts_count"), Some("0"));
+    }
+
+    #[test]
+    fn links_alternatives() {
+        // 1 and 2 recommend 3, 4 recommends itself
+        let mut records = vec![
+            record(1, Some(3)),
+            record(2, Some(3)),
+            record(3, None),
+            record(4, Some(4)),
+        ];
+        // The same recommendation in another source is listed under the same backlink
+        let mut dataset_notice = notice(Notice::UseAlternative("Mod 3".into(), Some(3), None));
+        dataset_notice.source = Source::JumliDataset("Example".into());
+        records[0].notices.push(dataset_notice);
+        // Historical recommendations are not linked
+        let mut historical = notice(Notice::UseAlternative("Mod 1".into(), Some(1), None));
+        historical.historical = true;
+        records[1].notices.push(historical);
+        let indices = indices(&records);
+        link_alternatives(&mut records, &indices);
+
+        let backlinks: Vec<_> = records[2]
+            .recommended_instead_of
+            .iter()
+            .map(|x| {
+                let sources: Vec<_> = x.sources.iter().map(|x| x.to_string()).collect();
+                (x.id.as_str(), sources)
+            })
+            .collect();
+        assert_eq!(
+            backlinks,
+            [
+                (
+                    "1",
+                    vec![
+                        Source::UseThisInsteadDatabase.to_string(),
+                        "JuMLi Dataset: Example".to_string()
+                    ]
+                ),
+                ("2", vec![Source::UseThisInsteadDatabase.to_string()]),
+            ]
+        );
+        assert_eq!(
+            records[2].recommended_instead_of[0].identifiers,
+            [ModIdentifier::WorkshopId(1)]
+        );
+        assert!(records[0].recommended_instead_of.is_empty());
+        assert!(records[3].recommended_instead_of.is_empty());
+    }
+
+    #[test]
+    fn creates_stubs_for_unknown_alternatives() {
+        // 1 recommends the unknown 2 and 3 recommends the known 1
+        let mut records = vec![record(1, Some(2)), record(3, Some(1))];
+        let mut historical = notice(Notice::UseAlternative("Mod 4".into(), Some(4), None));
+        historical.historical = true;
+        records[1].notices.push(historical);
+
+        let stubs = alternative_stubs(&records);
+        assert_eq!(stubs.len(), 1);
+        assert_eq!(stubs[0].identifiers, [ModIdentifier::WorkshopId(2)]);
+        assert_eq!(
+            stubs[0].id,
+            stable_id(&BTreeSet::from([ModIdentifier::WorkshopId(2)]))
+        );
+        assert!(stubs[0].notices.is_empty());
+
+        // Once the stub is part of the records, it is not created again
+        records.extend(stubs);
+        assert!(alternative_stubs(&records).is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+use crate::records::types::{Corroboration, Notice, NoticeRecord};
+
+/// Merge equivalent notices given by different sources into the first of them, returning how many
+/// were merged.
+///
+/// Notices are equivalent if they are of the same kind and recommend the same alternative or give
+/// the same text after normalization. Equivalent notices of a single source are kept apart, a source
+/// does not corroborate itself.
+pub(super) fn merge_duplicates(notices: &mut Vec<NoticeRecord>) -> usize {
+    let mut merged: Vec<NoticeRecord> = Vec::with_capacity(notices.len());
+    let mut keys = Vec::with_capacity(notices.len());
+    let mut count = 0;
+    for notice in notices.drain(..) {
+        let key = merge_key(&notice);
+        let source = notice.source.to_string();
+        match keys
+            .iter()
+            .zip(&merged)
+            .position(|(x, other)| *x == key && !has_source(other, &source))
+        {
+            Some(idx) => {
+                absorb(&mut merged[idx], notice);
+                count += 1;
+            }
+            None => {
+                keys.push(key);
+                merged.push(notice);
+            }
+        }
+    }
+
+    *notices = merged;
+    count
+}
+
+/// What makes notices equivalent: whether they are historical, their kind and their target or
+/// normalized text.
+fn merge_key(notice: &NoticeRecord) -> (bool, &'static str, String) {
+    let target = match &notice.notice {
+        Notice::UseAlternative(_, Some(workshop_id), _) => workshop_id.to_string(),
+        Notice::UseAlternative(name, None, _) => normalize(name),
+        Notice::BadPerformance(text) | Notice::Unstable(text) => {
+            text.as_deref().map(normalize).unwrap_or_default()
+        }
+        Notice::Bug(text) | Notice::Miscellaneous(text) => normalize(text),
+        Notice::OutOfDate => String::new(),
+    };
+    (notice.historical, notice.notice.title(), target)
+}
+
+/// Lowercase words without punctuation, e.g. `Causes lag!` and `causes  lag` are the same.
+fn normalize(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether a notice was given or corroborated by the source named `source`.
+fn has_source(notice: &NoticeRecord, source: &str) -> bool {
+    std::iter::once(&notice.source)
+        .chain(notice.corroborations.iter().map(|x| &x.source))
+        .any(|x| x.to_string() == source)
+}
+
+fn absorb(notice: &mut NoticeRecord, duplicate: NoticeRecord) {
+    // Details only given by the duplicate are kept. Equivalent alternatives share their workshop id.
+    if let (Notice::UseAlternative(_, _, reason), Notice::UseAlternative(_, _, other_reason)) =
+        (&mut notice.notice, duplicate.notice)
+        && reason.as_deref().is_none_or(str::is_empty)
+    {
+        *reason = other_reason;
+    }
+    if notice.context_url.is_none() {
+        notice.context_url = duplicate.context_url;
+    }
+
+    notice.corroborations.push(Corroboration {
+        source: duplicate.source,
+        date: duplicate.date,
+        certainty: duplicate.certainty,
+    });
+    notice.corroborations.extend(duplicate.corroborations);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::records::types::{Certainty, Source};
+
+    fn notice(source: Source, notice: Notice, certainty: Certainty) -> NoticeRecord {
+        NoticeRecord {
+            date: None,
+            notice,
+            certainty,
+            source,
+            context_url: None,
+            historical: false,
+            alternative_chain: None,
+            corroborations: Vec::new(),
+        }
+    }
+
+    fn dataset(name: &str) -> Source {
+        Source::JumliDataset(name.into())
+    }
+
+    fn lag(text: &str) -> Notice {
+        Notice::BadPerformance(Some(text.into()))
+    }
+
+    #[test]
+    fn merges_notices_of_different_sources() {
+        let mut notices = vec![
+            notice(dataset("a"), lag("Causes lag!"), Certainty::High),
+            notice(dataset("b"), lag("causes  lag"), Certainty::Low),
+            notice(dataset("b"), lag("Something else"), Certainty::Low),
+        ];
+        assert_eq!(merge_duplicates(&mut notices), 1);
+
+        assert_eq!(notices.len(), 2);
+        let corroborations = &notices[0].corroborations;
+        assert_eq!(corroborations.len(), 1);
+        assert_eq!(corroborations[0].source.to_string(), "JuMLi Dataset: b");
+        // The certainty of the merged notice is kept with it
+        assert!(matches!(notices[0].certainty, Certainty::High));
+        assert!(matches!(corroborations[0].certainty, Certainty::Low));
+    }
+
+    #[test]
+    fn keeps_notices_of_one_source_apart() {
+        let mut notices = vec![
+            notice(dataset("a"), lag("Causes lag"), Certainty::High),
+            notice(dataset("a"), lag("Causes lag"), Certainty::Medium),
+            notice(dataset("b"), lag("Causes lag"), Certainty::Low),
+            notice(dataset("b"), lag("Causes lag"), Certainty::Low),
+        ];
+        assert_eq!(merge_duplicates(&mut notices), 2);
+
+        // Each notice of `b` corroborates a different notice of `a`
+        assert_eq!(notices.len(), 2);
+        assert!(
+            notices
+                .iter()
+                .all(|x| x.source.to_string() == "JuMLi Dataset: a")
+        );
+        assert!(notices.iter().all(|x| x.corroborations.len() == 1));
+    }
+
+    #[test]
+    fn merges_alternatives_by_workshop_id() {
+        let alternative = |name: &str, reason: Option<&str>| {
+            Notice::UseAlternative(name.into(), Some(1000000001), reason.map(Into::into))
+        };
+        let mut notices = vec![
+            notice(
+                Source::UseThisInsteadDatabase,
+                alternative("Mod", None),
+                Certainty::Inapplicable,
+            ),
+            notice(
+                dataset("a"),
+                alternative("Mod (Continued)", Some("Updated")),
+                Certainty::High,
+            ),
+            notice(
+                dataset("a"),
+                Notice::UseAlternative("Mod".into(), None, None),
+                Certainty::High,
+            ),
+        ];
+        assert_eq!(merge_duplicates(&mut notices), 1);
+
+        assert_eq!(notices.len(), 2);
+        // The reason only the duplicate gives is kept
+        assert!(matches!(
+            &notices[0].notice,
+            Notice::UseAlternative(name, Some(1000000001), Some(reason))
+                if name == "Mod" && reason == "Updated"
+        ));
+    }
+
+    #[test]
+    fn keeps_historical_notices_apart() {
+        let mut notices = vec![
+            notice(dataset("a"), lag("Causes lag"), Certainty::High),
+            notice(dataset("b"), lag("Causes lag"), Certainty::High),
+        ];
+        notices[1].historical = true;
+        assert_eq!(merge_duplicates(&mut notices), 0);
+        assert_eq!(notices.len(), 2);
+    }
+}
@@ -1,20 +1,148 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap},
     error::Error,
+    pin::Pin,
+    time::Duration,
 };
 
-use tracing::info;
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use tracing::{error, info, warn};
 
 use crate::{
-    records::types::{IngestibleData, ModIdentifier, ModRecord},
-    sources::{Diagnostics, RecordSource},
+    records::types::{IngestibleData, ModIdentifier, ModRecord, Notice},
+    sources::{Diagnostics, RecordSource, cache::SnapshotCache},
 };
 
+mod alternatives;
+mod duplicates;
 pub mod types;
 
 pub struct DatabaseBuilder {
     raw_records: Vec<IngestibleData>,
+    /// Identifier groups referring to the same mod, with the index of the contributing source's
+    /// diagnostics
+    aliases: Vec<(usize, Vec<ModIdentifier>)>,
     named_diagnostics: Vec<(String, Diagnostics)>,
+    pending: Vec<Pin<Box<dyn Future<Output = FetchedSource>>>>,
+    cache: Option<SnapshotCache>,
+}
+
+/// How failures of a source are handled during ingestion.
+#[derive(Clone, Copy, Debug)]
+pub struct SourcePolicy {
+    /// Whether the build fails if the source cannot be fetched, instead of continuing without it
+    pub required: bool,
+    /// Time after which fetching is abandoned and counted as a failure
+    pub timeout: Duration,
+}
+
+impl Default for SourcePolicy {
+    fn default() -> Self {
+        Self {
+            required: false,
+            timeout: Duration::from_secs(300),
+        }
+    }
+}
+
+/// Outcome of fetching a single source.
+struct FetchedSource {
+    name: &'static str,
+    policy: SourcePolicy,
+    records: Vec<IngestibleData>,
+    aliases: Vec<Vec<ModIdentifier>>,
+    diagnostics: Diagnostics,
+    error: Option<String>,
+}
+
+async fn fetch_source(
+    mut source: Box<dyn RecordSource>,
+    policy: SourcePolicy,
+    cache: Option<SnapshotCache>,
+) -> FetchedSource {
+    let name = source.get_name();
+    let cache = cache.zip(source.cache_key());
+    // Diagnostics are only available after processing, collect log lines until then
+    let mut log_lines = Vec::new();
+
+    let snapshot = match &cache {
+        Some((cache, key)) if cache.is_offline() => {
+            info!("Using cached snapshot of {name}.");
+            cache
+                .load(key)
+                .map(|x| (x, true))
+                .map_err(|e| e.to_string())
+        }
+        _ => {
+            info!("Fetching {name}.");
+            match tokio::time::timeout(policy.timeout, source.fetch_snapshot()).await {
+                Ok(Ok(snapshot)) => {
+                    if let Some((cache, key)) = &cache
+                        && let Err(e) = cache.save(key, &snapshot)
+                    {
+                        warn!("Unable to cache snapshot of {name}: {e}");
+                        log_lines.push(format!("Unable to cache snapshot: {e}"));
+                    }
+                    Ok((snapshot, false))
+                }
+                result => {
+                    let e = match result {
+                        Ok(Err(e)) => e.to_string(),
+                        _ => format!("Timed out after {}s.", policy.timeout.as_secs()),
+                    };
+                    match cache.as_ref().map(|(cache, key)| cache.load(key)) {
+                        Some(Ok(snapshot)) => {
+                            warn!("Failed to fetch {name}, using cached snapshot: {e}");
+                            log_lines
+                                .push(format!("Failed to fetch, using a cached snapshot: {e}"));
+                            Ok((snapshot, true))
+                        }
+                        _ => Err(e),
+                    }
+                }
+            }
+        }
+    };
+
+    let (commit, snapshot_time, error) = match snapshot {
+        Ok((snapshot, cached)) => {
+            let commit = snapshot.commit.clone();
+            let snapshot_time = cached.then_some(snapshot.fetched_at);
+            let error = source.process(snapshot).err().map(|e| e.to_string());
+            (commit, snapshot_time, error)
+        }
+        Err(e) => (None, None, Some(e)),
+    };
+
+    // Records of a failed fetch may be incomplete, so none of them are used
+    let (records, aliases) = match error {
+        None => (
+            source.get_records().map(std::mem::take).unwrap_or_default(),
+            source.get_aliases().map(std::mem::take).unwrap_or_default(),
+        ),
+        Some(_) => (Vec::new(), Vec::new()),
+    };
+
+    let mut diagnostics = source.take_diagnostics();
+    if let Some(commit) = &commit {
+        diagnostics.add_commit_info(commit);
+    }
+    if let Some(time) = snapshot_time {
+        diagnostics.set_snapshot_time(time);
+    }
+    for line in log_lines {
+        diagnostics.log(line);
+    }
+
+    FetchedSource {
+        name,
+        policy,
+        records,
+        aliases,
+        diagnostics,
+        error,
+    }
 }
 
 pub struct Database {
@@ -23,6 +151,39 @@ pub struct Database {
     pub named_diagnostics: Vec<(String, Diagnostics)>,
 }
 
+impl Database {
+    /// Most recent commit time reported by any source, if known.
+    pub fn latest_commit_time(&self) -> Option<DateTime<Utc>> {
+        self.named_diagnostics
+            .iter()
+            .filter_map(|(_, diagnostics)| diagnostics.get_commit_time())
+            .max()
+    }
+
+    /// Find the record for a mod, trying package ids before workshop ids.
+    pub fn find_record(&self, identifiers: &[ModIdentifier]) -> Option<&ModRecord> {
+        let package_ids = identifiers
+            .iter()
+            .filter(|x| matches!(x, ModIdentifier::PackageId(_)));
+        let workshop_ids = identifiers
+            .iter()
+            .filter(|x| matches!(x, ModIdentifier::WorkshopId(_)));
+
+        package_ids
+            .chain(workshop_ids)
+            .find_map(|identifier| self.indices.get(&identifier.to_string()))
+            .map(|idx| &self.records[*idx])
+    }
+
+    /// Find the record for the mod a `UseAlternative` notice recommends.
+    pub fn alternative_record(&self, notice: &Notice) -> Option<&ModRecord> {
+        let Notice::UseAlternative(_, Some(workshop_id), _) = notice else {
+            return None;
+        };
+        self.find_record(&[ModIdentifier::WorkshopId(*workshop_id)])
+    }
+}
+
 struct UnionFind {
     parent: Vec<usize>,
     rank: Vec<usize>,
@@ -43,11 +204,12 @@ impl UnionFind {
         self.parent[x]
     }
 
-    fn union(&mut self, x: usize, y: usize) {
+    /// Merge the sets containing `x` and `y`, returning whether they were separate.
+    fn union(&mut self, x: usize, y: usize) -> bool {
         let root_x = self.find(x);
         let root_y = self.find(y);
         if root_x == root_y {
-            return;
+            return false;
         }
 
         if self.rank[root_x] < self.rank[root_y] {
@@ -58,6 +220,13 @@ impl UnionFind {
             self.parent[root_y] = root_x;
             self.rank[root_x] += 1;
         }
+        true
+    }
+}
+
+impl Default for DatabaseBuilder {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -65,24 +234,67 @@ impl DatabaseBuilder {
     pub fn new() -> Self {
         Self {
             raw_records: Vec::new(),
+            aliases: Vec::new(),
             named_diagnostics: Vec::new(),
+            pending: Vec::new(),
+            cache: None,
+        }
+    }
+
+    /// Create a builder that caches source snapshots, falling back to them if fetching fails.
+    pub fn with_cache(cache: SnapshotCache) -> Self {
+        Self {
+            cache: Some(cache),
+            ..Self::new()
         }
     }
 
-    pub async fn ingest_from(
-        &mut self,
-        mut source: impl RecordSource,
-    ) -> Result<(), Box<dyn Error>> {
-        source.fetch().await?;
+    /// Queue a source to be fetched by `ingest_all`.
+    pub fn add_source(&mut self, source: Box<dyn RecordSource>, policy: SourcePolicy) {
+        self.pending
+            .push(Box::pin(fetch_source(source, policy, self.cache.clone())));
+    }
+
+    /// Fetch all queued sources concurrently. Failed sources are recorded in their diagnostics and
+    /// skipped, unless they are required.
+    pub async fn ingest_all(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut failed_required = Vec::new();
+        // join_all keeps the order sources were added in, which keeps the output reproducible
+        for mut source in join_all(std::mem::take(&mut self.pending)).await {
+            source.diagnostics.add_property(
+                "policy",
+                if source.policy.required {
+                    "required"
+                } else {
+                    "optional"
+                },
+            );
+
+            if let Some(e) = source.error {
+                error!("Failed to fetch {}: {e}", source.name);
+                source
+                    .diagnostics
+                    .log_error(format!("Failed to fetch, no records were ingested: {e}"));
+                if source.policy.required {
+                    failed_required.push(source.name);
+                }
+            }
 
-        if let Some(records) = source.get_records() {
-            self.raw_records.append(records);
+            self.raw_records.append(&mut source.records);
+            let diagnostics_idx = self.named_diagnostics.len();
+            self.aliases.extend(
+                source
+                    .aliases
+                    .into_iter()
+                    .map(|group| (diagnostics_idx, group)),
+            );
+            self.named_diagnostics
+                .push((format!("Source: {}", source.name), source.diagnostics));
         }
 
-        self.named_diagnostics.push((
-            format!("Source: {}", source.get_name()),
-            std::mem::take(&mut source.get_diagnostics()),
-        ));
+        if !failed_required.is_empty() {
+            return Err(format!("Required sources failed: {}", failed_required.join(", ")).into());
+        }
         Ok(())
     }
 
@@ -108,7 +320,7 @@ impl DatabaseBuilder {
         }
 
         let mut union_find = UnionFind::new(self.raw_records.len());
-        for indices in ident_to_records.into_values() {
+        for indices in ident_to_records.values() {
             if indices.len() < 2 {
                 continue;
             }
@@ -118,6 +330,45 @@ impl DatabaseBuilder {
             }
         }
 
+        // Aliases merge records that list the same mod by different identifiers, the merges are
+        // counted for the diagnostics of the contributing source
+        // Package ids in aliases are matched case-insensitively, as RimWorld does
+        let mut package_id_records: HashMap<String, Vec<usize>> = HashMap::new();
+        for (identifier, indices) in &ident_to_records {
+            if let ModIdentifier::PackageId(id) = identifier {
+                package_id_records
+                    .entry(id.trim().to_lowercase())
+                    .or_default()
+                    .extend(indices);
+            }
+        }
+        let mut alias_merges: BTreeMap<usize, usize> = BTreeMap::new();
+        for (diagnostics_idx, group) in &self.aliases {
+            let merges = alias_merges.entry(*diagnostics_idx).or_default();
+            let mut indices = group
+                .iter()
+                .filter_map(|identifier| match identifier {
+                    ModIdentifier::PackageId(id) => {
+                        package_id_records.get(&id.trim().to_lowercase())
+                    }
+                    ModIdentifier::WorkshopId(_) => ident_to_records.get(identifier),
+                })
+                .flatten();
+            let Some(&first) = indices.next() else {
+                continue;
+            };
+            for &idx in indices {
+                if union_find.union(first, idx) {
+                    *merges += 1;
+                }
+            }
+        }
+        for (diagnostics_idx, merges) in alias_merges {
+            self.named_diagnostics[diagnostics_idx]
+                .1
+                .add_property("alias_merges", merges.to_string());
+        }
+
         let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
         for i in 0..self.raw_records.len() {
             let root = union_find.find(i);
@@ -125,25 +376,52 @@ impl DatabaseBuilder {
         }
 
         let mut final_records = Vec::new();
-        let mut final_indices = HashMap::new();
+        let mut merged_notices = 0;
         for indices in groups.into_values() {
-            let mut identifiers = HashSet::new();
+            let mut identifiers = BTreeSet::new();
             let mut notices = Vec::new();
             for idx in indices {
                 identifiers.extend(self.raw_records[idx].identifiers.iter().cloned());
                 notices.append(&mut self.raw_records[idx].notices);
             }
+            // Sources are ingested in the order they are configured, ordering the notices by
+            // source keeps the merged notices independent of it
+            notices.sort_by_cached_key(|x| x.source.to_string());
+            merged_notices += duplicates::merge_duplicates(&mut notices);
 
             final_records.push(ModRecord {
+                id: stable_id(&identifiers),
                 notices,
-                identifiers: identifiers.iter().cloned().collect(),
+                identifiers: identifiers.into_iter().collect(),
+                recommended_instead_of: Vec::new(),
             });
+        }
+        // Alternatives without notices of their own get a record to list what they replace
+        let stubs = alternatives::alternative_stubs(&final_records);
+        final_records.extend(stubs);
 
-            for identifier in identifiers.into_iter() {
-                final_indices.insert(identifier.to_string(), final_records.len() - 1);
+        deduplicate_ids(&mut final_records);
+        // Group iteration order is random, sort to keep output reproducible
+        final_records.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut final_indices = HashMap::new();
+        for (idx, record) in final_records.iter().enumerate() {
+            for identifier in &record.identifiers {
+                final_indices.insert(identifier.to_string(), idx);
             }
         }
 
+        alternatives::link_alternatives(&mut final_records, &final_indices);
+        let alternative_diagnostics =
+            alternatives::check_alternatives(&mut final_records, &final_indices);
+        self.named_diagnostics.push((
+            "Alternative Recommendations".into(),
+            alternative_diagnostics,
+        ));
+
+        if merged_notices > 0 {
+            info!("Merged {merged_notices} notices given by several sources.");
+        }
         info!(
             "Finalized database with {} unique entries.",
             final_records.len()
@@ -156,3 +434,329 @@ impl DatabaseBuilder {
         }
     }
 }
+
+/// Derive a record ID that stays the same across builds as long as the record's identifiers do.
+///
+/// Uses the lowest workshop id, falling back to the lowest package id, lowercased and restricted to
+/// characters that are safe in file names and URLs.
+fn stable_id(identifiers: &BTreeSet<ModIdentifier>) -> String {
+    let lowest_workshop_id = identifiers
+        .iter()
+        .filter(|x| !x.is_invalid())
+        .filter_map(|x| match x {
+            ModIdentifier::WorkshopId(id) => Some(*id),
+            ModIdentifier::PackageId(_) => None,
+        })
+        .min();
+    if let Some(id) = lowest_workshop_id {
+        return id.to_string();
+    }
+
+    identifiers
+        .iter()
+        .filter(|x| !x.is_invalid())
+        .filter_map(|x| match x {
+            ModIdentifier::PackageId(id) => Some(
+                id.trim()
+                    .to_lowercase()
+                    .chars()
+                    .map(|c| match c {
+                        'a'..='z' | '0'..='9' | '.' | '_' | '-' => c,
+                        _ => '_',
+                    })
+                    .collect::<String>(),
+            ),
+            ModIdentifier::WorkshopId(_) => None,
+        })
+        .min()
+        .unwrap_or_else(|| "invalid".into())
+}
+
+/// Disambiguate colliding IDs (e.g. package ids differing only in case) with a `~n` suffix.
+/// `~` never appears in derived IDs, so suffixed IDs cannot collide with derived ones.
+fn deduplicate_ids(records: &mut [ModRecord]) {
+    let mut order: Vec<usize> = (0..records.len()).collect();
+    order.sort_by_cached_key(|&idx| {
+        let mut identifiers: Vec<String> = records[idx]
+            .identifiers
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        identifiers.sort();
+        (records[idx].id.clone(), identifiers)
+    });
+
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for idx in order {
+        let count = seen.entry(records[idx].id.clone()).or_default();
+        if *count > 0 {
+            records[idx].id = format!("{}~{count}", records[idx].id);
+        }
+        *count += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use futures::future::LocalBoxFuture;
+
+    use super::*;
+    use crate::{
+        records::types::{Certainty, NoticeRecord, Source},
+        sources::{cache::Snapshot, identity_aliases::IdentityAliases},
+    };
+
+    /// Source yielding fixed records under a name.
+    struct Fixed(&'static str, Vec<IngestibleData>);
+
+    impl RecordSource for Fixed {
+        fn fetch_snapshot(&mut self) -> LocalBoxFuture<'_, Result<Snapshot, Box<dyn Error>>> {
+            Box::pin(async { Ok(Snapshot::new(None)) })
+        }
+
+        fn process(&mut self, _: Snapshot) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+
+        fn cache_key(&self) -> Option<String> {
+            None
+        }
+
+        fn get_records(&mut self) -> Option<&mut Vec<IngestibleData>> {
+            Some(&mut self.1)
+        }
+
+        fn take_diagnostics(&mut self) -> Diagnostics {
+            Diagnostics::new()
+        }
+
+        fn get_name(&self) -> &'static str {
+            self.0
+        }
+    }
+
+    fn record(identifier: ModIdentifier) -> IngestibleData {
+        IngestibleData {
+            identifiers: vec![identifier],
+            notices: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn aliases_merge_records() {
+        let mut builder = DatabaseBuilder::new();
+        builder.add_source(
+            Box::new(Fixed(
+                "Fixed",
+                vec![
+                    record(ModIdentifier::PackageId("unlimitedhugs.allowtool".into())),
+                    record(ModIdentifier::WorkshopId(761421485)),
+                    record(ModIdentifier::WorkshopId(1134165362)),
+                ],
+            )),
+            SourcePolicy::default(),
+        );
+        builder.add_source(
+            Box::new(IdentityAliases::new(PathBuf::from(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/fixtures/identity_aliases/aliases.json"
+            )))),
+            SourcePolicy::default(),
+        );
+        builder.ingest_all().await.unwrap();
+        let db = builder.finalize().await;
+
+        // The alias spells the package id in different case
+        assert_eq!(db.records.len(), 2);
+        let allow_tool = db
+            .find_record(&[ModIdentifier::WorkshopId(761421485)])
+            .unwrap();
+        assert!(
+            allow_tool
+                .identifiers
+                .contains(&ModIdentifier::PackageId("unlimitedhugs.allowtool".into()))
+        );
+
+        let (_, diagnostics) = db
+            .named_diagnostics
+            .iter()
+            .find(|(name, _)| name == "Source: Identity Aliases")
+            .unwrap();
+        assert!(
+            diagnostics
+                .get_properties()
+                .unwrap()
+                .contains(&("alias_merges".into(), "1".into()))
+        );
+    }
+
+    fn mod_record(identifiers: &[ModIdentifier]) -> ModRecord {
+        let identifiers: BTreeSet<_> = identifiers.iter().cloned().collect();
+        ModRecord {
+            id: stable_id(&identifiers),
+            notices: Vec::new(),
+            identifiers: identifiers.into_iter().collect(),
+            recommended_instead_of: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn stable_ids_ignore_identifier_order() {
+        let identifiers = [
+            ModIdentifier::PackageId("author.mod".into()),
+            ModIdentifier::WorkshopId(2000000002),
+            ModIdentifier::WorkshopId(1000000001),
+        ];
+        let reversed: Vec<_> = identifiers.iter().rev().cloned().collect();
+        assert_eq!(mod_record(&identifiers).id, mod_record(&reversed).id);
+    }
+
+    #[test]
+    fn stable_ids_prefer_workshop_ids() {
+        // The lowest workshop id is used
+        let record = mod_record(&[
+            ModIdentifier::PackageId("author.mod".into()),
+            ModIdentifier::WorkshopId(2000000002),
+            ModIdentifier::WorkshopId(1000000001),
+        ]);
+        assert_eq!(record.id, "1000000001");
+
+        // Invalid workshop ids are skipped, package ids are lowercased and made safe for URLs
+        let record = mod_record(&[
+            ModIdentifier::PackageId("Author.Mod Continued/2".into()),
+            ModIdentifier::WorkshopId(0),
+        ]);
+        assert_eq!(record.id, "author.mod_continued_2");
+
+        let record = mod_record(&[
+            ModIdentifier::PackageId("b.mod".into()),
+            ModIdentifier::PackageId("a.mod".into()),
+        ]);
+        assert_eq!(record.id, "a.mod");
+        assert_eq!(mod_record(&[]).id, "invalid");
+    }
+
+    #[test]
+    fn colliding_ids_are_suffixed() {
+        let ids = |order: &[usize]| {
+            let package_ids = ["author.mod", "Author.Mod", "AUTHOR.MOD", "other.mod"];
+            let mut records: Vec<_> = order
+                .iter()
+                .map(|&x| mod_record(&[ModIdentifier::PackageId(package_ids[x].into())]))
+                .collect();
+            deduplicate_ids(&mut records);
+            let mut ids: Vec<_> = records
+                .into_iter()
+                .map(|x| (x.identifiers[0].to_string(), x.id))
+                .collect();
+            ids.sort();
+            ids
+        };
+
+        let expected = [
+            ("AUTHOR.MOD".to_string(), "author.mod".to_string()),
+            ("Author.Mod".into(), "author.mod~1".into()),
+            ("author.mod".into(), "author.mod~2".into()),
+            ("other.mod".into(), "other.mod".into()),
+        ];
+        // Suffixes depend on the identifiers, not on the order records arrive in
+        assert_eq!(ids(&[0, 1, 2, 3]), expected);
+        assert_eq!(ids(&[3, 2, 1, 0]), expected);
+        assert_eq!(ids(&[1, 3, 0, 2]), expected);
+    }
+
+    fn notice(source: Source, notice: Notice) -> NoticeRecord {
+        NoticeRecord {
+            date: None,
+            notice,
+            certainty: Certainty::High,
+            source,
+            context_url: None,
+            historical: false,
+            alternative_chain: None,
+            corroborations: Vec::new(),
+        }
+    }
+
+    /// Records and index as written to the output.
+    async fn build(sources: Vec<Fixed>) -> (String, String) {
+        let mut builder = DatabaseBuilder::new();
+        for source in sources {
+            builder.add_source(Box::new(source), SourcePolicy::default());
+        }
+        builder.ingest_all().await.unwrap();
+        let db = builder.finalize().await;
+        let index: BTreeMap<_, _> = db
+            .indices
+            .iter()
+            .map(|(identifier, idx)| (identifier, &db.records[*idx].id))
+            .collect();
+        (
+            serde_json::to_string(&db.records).unwrap(),
+            serde_json::to_string(&index).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn output_does_not_depend_on_source_order() {
+        let dataset = || {
+            let source = || Source::JumliDataset("dataset".into());
+            Fixed(
+                "Dataset",
+                vec![
+                    IngestibleData {
+                        identifiers: vec![ModIdentifier::WorkshopId(1000000001)],
+                        notices: vec![notice(
+                            source(),
+                            Notice::BadPerformance(Some("Lags".into())),
+                        )],
+                    },
+                    IngestibleData {
+                        identifiers: vec![
+                            ModIdentifier::PackageId("author.old".into()),
+                            ModIdentifier::WorkshopId(1000000002),
+                        ],
+                        notices: vec![notice(
+                            source(),
+                            Notice::UseAlternative("New".into(), Some(1000000003), None),
+                        )],
+                    },
+                ],
+            )
+        };
+        let uti = || {
+            let source = || Source::UseThisInsteadDatabase;
+            Fixed(
+                "Use This Instead",
+                vec![
+                    IngestibleData {
+                        identifiers: vec![ModIdentifier::PackageId("author.old".into())],
+                        notices: vec![notice(
+                            source(),
+                            Notice::UseAlternative("author.new".into(), Some(1000000003), None),
+                        )],
+                    },
+                    IngestibleData {
+                        identifiers: vec![ModIdentifier::WorkshopId(1000000001)],
+                        notices: vec![notice(
+                            source(),
+                            Notice::BadPerformance(Some("lags!".into())),
+                        )],
+                    },
+                    IngestibleData {
+                        identifiers: vec![ModIdentifier::PackageId("Author.Old".into())],
+                        notices: vec![notice(source(), Notice::OutOfDate)],
+                    },
+                ],
+            )
+        };
+
+        let first = build(vec![dataset(), uti()]).await;
+        let second = build(vec![uti(), dataset()]).await;
+        assert_eq!(first, second);
+        // The notices of both sources were merged
+        assert!(first.0.contains("corroborations"));
+    }
+}
@@ -1,4 +1,7 @@
+use std::fmt::Display;
+
 use chrono::NaiveDate;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -15,11 +18,28 @@ pub struct ModRecordIndex {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ModRecord {
+    /// Stable ID used for report URLs, see `records::stable_id`
+    pub id: String,
     pub notices: Vec<NoticeRecord>,
     pub identifiers: Vec<ModIdentifier>,
+    /// Mods whose notices recommend this one as their alternative, set by `finalize`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recommended_instead_of: Vec<Backlink>,
+}
+
+/// A mod recommending another one as its alternative.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Backlink {
+    /// Stable ID of the recommending mod's record
+    pub id: String,
+    pub identifiers: Vec<ModIdentifier>,
+    /// Sources of the recommendations
+    pub sources: Vec<Source>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Eq, Hash)]
+#[derive(
+    Serialize, Deserialize, JsonSchema, Debug, PartialEq, Clone, Eq, Hash, PartialOrd, Ord,
+)]
 pub enum ModIdentifier {
     PackageId(String),
     WorkshopId(u64),
@@ -32,13 +52,25 @@ impl ModIdentifier {
             Self::WorkshopId(id) => *id == 0,
         }
     }
+
+    /// Whether the identifier can be used as a path segment for redirect pages.
+    pub fn is_redirectable(&self) -> bool {
+        match self {
+            // `.` and `..` would place the redirect in or above the package directory
+            Self::PackageId(id) => {
+                !matches!(id.as_str(), "" | "." | "..")
+                    && id.chars().all(|c| c != '/' && !c.is_control())
+            }
+            Self::WorkshopId(_) => true,
+        }
+    }
 }
 
-impl ToString for ModIdentifier {
-    fn to_string(&self) -> String {
+impl Display for ModIdentifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::PackageId(id) => id.clone(),
-            Self::WorkshopId(id) => id.to_string(),
+            Self::PackageId(id) => write!(f, "{id}"),
+            Self::WorkshopId(id) => write!(f, "{id}"),
         }
     }
 }
@@ -51,20 +83,85 @@ pub struct NoticeRecord {
     pub source: Source,
     pub context_url: Option<String>,
     pub historical: bool,
+    /// Where following the alternative's own recommendations leads, set by `finalize` for
+    /// `UseAlternative` notices whose alternative is itself superseded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alternative_chain: Option<AlternativeChain>,
+    /// Other sources giving an equivalent notice, merged into this one by `finalize`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub corroborations: Vec<Corroboration>,
+}
+
+/// Another source giving an equivalent notice.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Corroboration {
+    pub source: Source,
+    pub date: Option<NaiveDate>,
+    /// How sure the other source is about the notice
+    pub certainty: Certainty,
 }
 
+/// Alternatives recommended in turn after the direct alternative of a `UseAlternative` notice.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AlternativeChain {
+    /// Further alternatives, the last one being the final recommendation
+    pub steps: Vec<Alternative>,
+    /// Whether the recommendations lead back to a mod already in the chain
+    pub cycle: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Alternative {
+    pub name: String,
+    pub workshop_id: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
 pub enum Notice {
+    /// Explanation
     BadPerformance(Option<String>),
+    /// Name of the alternative, its workshop id and the reason to switch
     UseAlternative(String, Option<u64>, Option<String>),
+    /// Description of the bug
     Bug(String),
+    /// Explanation
     Unstable(Option<String>),
     OutOfDate,
 
+    /// Note
     Miscellaneous(String),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl Notice {
+    /// Short name of the kind of notice, as used in headings.
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::BadPerformance(_) => "Bad Performance",
+            Self::UseAlternative(..) => "Better Alternative Available",
+            Self::Bug(_) => "Current Bug",
+            Self::Unstable(_) => "Unstable",
+            Self::OutOfDate => "Out Of Date",
+            Self::Miscellaneous(_) => "Note",
+        }
+    }
+}
+
+impl NoticeRecord {
+    /// Whether the notice advises against using the mod, as opposed to recommending another one
+    /// or noting something about it.
+    pub fn is_concern(&self) -> bool {
+        !self.historical
+            && matches!(
+                self.notice,
+                Notice::BadPerformance(_)
+                    | Notice::Bug(_)
+                    | Notice::Unstable(_)
+                    | Notice::OutOfDate
+            )
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub enum Certainty {
     High,
     Medium,
@@ -78,14 +175,17 @@ pub enum Source {
     JumliDataset(String),
     UseThisInsteadDatabase,
     WorkshopDatabase,
+    /// `About.xml` of an installed mod
+    ModMetadata,
 }
 
-impl ToString for Source {
-    fn to_string(&self) -> String {
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Source::JumliDataset(name) => format!("JuMLi Dataset: {name}"),
-            Source::UseThisInsteadDatabase => "Use This Instead Database".into(),
-            Source::WorkshopDatabase => "Steam Workshop Database".into(),
+            Source::JumliDataset(name) => write!(f, "JuMLi Dataset: {name}"),
+            Source::UseThisInsteadDatabase => write!(f, "Use This Instead Database"),
+            Source::WorkshopDatabase => write!(f, "Steam Workshop Database"),
+            Source::ModMetadata => write!(f, "Installed Mod Metadata"),
         }
     }
 }
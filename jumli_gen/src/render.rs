@@ -1,72 +1,134 @@
-use chrono::Utc;
-use maud::{PreEscaped, html};
+use chrono::{DateTime, TimeDelta, Utc};
+use maud::{Markup, PreEscaped, html};
+use serde_json::json;
 
-use crate::records::{
-    Database,
-    types::{ModRecord, Notice, NoticeRecord},
+use crate::{
+    config::SiteConfig,
+    records::{
+        Database,
+        types::{ModRecord, Notice, NoticeRecord},
+    },
 };
 
 pub trait RenderHtml {
-    fn render_html(&self) -> String;
+    fn render_html(&self, config: &SiteConfig, db: &Database) -> String;
 }
 
-impl RenderHtml for NoticeRecord {
-    fn render_html(&self) -> String {
-        html! {
-            div.notice {
-                @match &self.notice {
-                    Notice::BadPerformance(reason) => {
-                        strong { "Bad Performance"}
-                        @if let Some(reason) = reason {
-                            p { (reason) }
-                        } @else {
-                            p { "No reason provided." }
-                        }
-                    },
-                    Notice::UseAlternative(alternative_name, workshop_id, reason) => {
-                        strong { (format!("Better Alternative Available: {alternative_name}")) }
-                        @if let Some(workshop_id) = workshop_id {
-                            a class="workshop-alternative" href=(format!("https://steamcommunity.com/sharedfiles/filedetails/?id={workshop_id}")) { "Steam Workshop" }
-                        }
-                        @if let Some(reason) = reason {
-                            p { (reason) }
-                        } @else {
-                            p { "No reason provided." }
-                        }
-                    },
-                    Notice::Bug(description) => {
-                        strong { "Current Bug" }
-                        p { (description) }
-                    },
-                    Notice::Unstable(description) => {
-                        strong { "Unstable" }
-                        @if let Some(description) = description {
-                            p { (description) }
-                        } @else {
-                            p { "No description provided." }
+/// Render a notice of the record with id `record_id`.
+fn render_notice(
+    notice: &NoticeRecord,
+    record_id: &str,
+    config: &SiteConfig,
+    db: &Database,
+) -> String {
+    html! {
+        div.notice {
+            @match &notice.notice {
+                Notice::BadPerformance(reason) => {
+                    strong { "Bad Performance"}
+                    @if let Some(reason) = reason {
+                        p { (reason) }
+                    } @else {
+                        p { "No reason provided." }
+                    }
+                },
+                Notice::UseAlternative(alternative_name, workshop_id, reason) => {
+                    strong { (format!("Better Alternative Available: {alternative_name}")) }
+                    @if let Some(workshop_id) = workshop_id {
+                        a class="workshop-alternative" href=(format!("https://steamcommunity.com/sharedfiles/filedetails/?id={workshop_id}")) { "Steam Workshop" }
+                    }
+                    @if let Some(reason) = reason {
+                        p { (reason) }
+                    } @else {
+                        p { "No reason provided." }
+                    }
+                    @if let Some(chain) = &notice.alternative_chain {
+                        p.alternative-chain {
+                            @if chain.cycle {
+                                "The recommended alternatives lead in a circle: "
+                            } @else if let Some(last) = chain.steps.last() {
+                                "This alternative is itself superseded, the final recommendation is "
+                                strong { (last.name) } ": "
+                            }
+                            (alternative_link(alternative_name, *workshop_id))
+                            @for step in &chain.steps {
+                                " → " (alternative_link(&step.name, step.workshop_id))
+                            }
                         }
                     }
-                    Notice::OutOfDate => {
-                        strong { "Out Of Date"}
-                        p { "This mod is not tagged as being compatible with the latest RimWorld version. If you use it anyway, it will likely lead to game-breaking bugs."}
+                    // A mod recommending another upload of itself shares its record, whose notices are shown already
+                    @if let Some(alternative) = db.alternative_record(&notice.notice).filter(|x| x.id != record_id) {
+                        @let concerns: Vec<_> = alternative.notices.iter().filter(|x| x.is_concern()).collect();
+                        @if !concerns.is_empty() {
+                            div.alternative-concerns {
+                                p { "Note that the alternative has notices of its own:" }
+                                ul {
+                                    @for concern in concerns {
+                                        li {
+                                            (concern.notice.title()) " (" (concern.source.to_string())
+                                            @if let Some(date) = concern.date { ", " (date) }
+                                            ")"
+                                        }
+                                    }
+                                }
+                                a href=(format!("/{}/{}.html", config.paths.mod_reports, alternative.id)) { "See its report" }
+                            }
+                        }
                     }
-                    Notice::Miscellaneous(body) => {
-                        strong { "Note" }
-                        p { (body) }
+                },
+                Notice::Bug(description) => {
+                    strong { "Current Bug" }
+                    p { (description) }
+                },
+                Notice::Unstable(description) => {
+                    strong { "Unstable" }
+                    @if let Some(description) = description {
+                        p { (description) }
+                    } @else {
+                        p { "No description provided." }
                     }
                 }
-                @if let Some(context_url) = &self.context_url {
-                    a.context href=(context_url) { "Click here for additional context" }
+                Notice::OutOfDate => {
+                    strong { "Out Of Date"}
+                    p { "This mod is not tagged as being compatible with the latest RimWorld version (" (config.latest_rimworld_release) "). If you use it anyway, it will likely lead to game-breaking bugs."}
+                }
+                Notice::Miscellaneous(body) => {
+                    strong { "Note" }
+                    p { (body) }
+                }
+            }
+            @if let Some(context_url) = &notice.context_url {
+                a.context href=(context_url) { "Click here for additional context" }
+            }
+            p.source { (notice.source.to_string()) @if let Some(date) = notice.date { " (" (date) ")" } }
+            @if !notice.corroborations.is_empty() {
+                p.source {
+                    "Also reported by "
+                    @for (idx, corroboration) in notice.corroborations.iter().enumerate() {
+                        @if idx > 0 { ", " }
+                        (corroboration.source.to_string())
+                        @if let Some(date) = corroboration.date { " (" (date) ")" }
+                    }
                 }
-                p.source { (self.source.to_string()) @if let Some(date) = self.date { " (" (date) ")" } }
             }
         }
-        .into_string()
+    }
+    .into_string()
+}
+
+/// Name of an alternative, linked to its workshop page if known.
+fn alternative_link(name: &str, workshop_id: Option<u64>) -> Markup {
+    html! {
+        @if let Some(workshop_id) = workshop_id {
+            a href=(format!("https://steamcommunity.com/sharedfiles/filedetails/?id={workshop_id}")) { (name) }
+        } @else {
+            (name)
+        }
     }
 }
 
 impl RenderHtml for ModRecord {
-    fn render_html(&self) -> String {
+    fn render_html(&self, config: &SiteConfig, db: &Database) -> String {
         html! {
             head {
                 link rel="stylesheet" href="/report.css" {}
@@ -79,11 +141,26 @@ impl RenderHtml for ModRecord {
                         li { (identifier.to_string()) }
                     }
                 }
-                h4 { "Notices" }
-                div.notices {
-                    @for notice in &self.notices {
-                        @if !notice.historical {
-                            (PreEscaped(notice.render_html()))
+                @if self.notices.iter().any(|x| !x.historical) {
+                    h4 { "Notices" }
+                    div.notices {
+                        @for notice in &self.notices {
+                            @if !notice.historical {
+                                (PreEscaped(render_notice(notice, &self.id, config, db)))
+                            }
+                        }
+                    }
+                }
+                @if !self.recommended_instead_of.is_empty() {
+                    h4 { "Recommended as an alternative to" }
+                    ul.backlinks {
+                        @for backlink in &self.recommended_instead_of {
+                            li {
+                                a href=(format!("/{}/{}.html", config.paths.mod_reports, backlink.id)) {
+                                    (backlink.identifiers.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(", "))
+                                }
+                                " (" (backlink.sources.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(", ")) ")"
+                            }
                         }
                     }
                 }
@@ -105,7 +182,7 @@ pub fn frame_html(destination: String) -> String {
     .into_string()
 }
 
-pub fn render_diagnostics(db: &Database) -> String {
+pub fn render_diagnostics(db: &Database, config: &SiteConfig, build_time: DateTime<Utc>) -> String {
     html! {
         head {
             link rel="stylesheet" href="/index.css" {}
@@ -116,10 +193,22 @@ pub fn render_diagnostics(db: &Database) -> String {
                 a href="/" { "Home" }
             }
             main {
-                p { "JuMLi was last built around " code { (Utc::now().to_rfc3339()) } "." }
+                p { "JuMLi was last built around " code { (build_time.to_rfc3339()) } "." }
                 p { "Database currently contains " (db.records.len()) " consolidated mod records."}
+                p { "Mods are checked against RimWorld " (config.latest_rimworld_release) "." }
+                p {
+                    "These diagnostics are also available as "
+                    a href=(format!("/{}", config.paths.diagnostics_data)) { "JSON" }
+                    ", including every problem found in the sources' data."
+                }
                 @for (name, diag) in &db.named_diagnostics {
                     h3 { (name) }
+                    @if let Some(snapshot_time) = diag.get_snapshot_time() {
+                        p {
+                            "Using a cached snapshot fetched at " code { (snapshot_time.to_rfc3339()) }
+                            ", " (format_age(build_time - snapshot_time)) " before this build."
+                        }
+                    }
                     @if let Some(props) = diag.get_properties() {
                         table class="diagnostics" {
                             @for (key, value) in props {
@@ -130,6 +219,29 @@ pub fn render_diagnostics(db: &Database) -> String {
                             }
                         }
                     }
+                    @if let Some(entries) = diag.get_entries() {
+                        details {
+                            summary { (entries.len()) " problems found" }
+                            table class="diagnostics" {
+                                tr {
+                                    th { "Location" }
+                                    th { "Problem" }
+                                    th { "Value" }
+                                }
+                                @for entry in entries {
+                                    tr {
+                                        td { code { (entry.location) } }
+                                        td { (entry.message) " (" code { (entry.kind) } ")" }
+                                        td {
+                                            @if let Some(value) = &entry.value {
+                                                code { (value) }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
                     @if let Some(logs) = diag.get_logs() {
                         code {
                             (logs.join("\n"))
@@ -141,3 +253,133 @@ pub fn render_diagnostics(db: &Database) -> String {
     }
     .into_string()
 }
+
+/// Diagnostics of all sources as JSON, e.g. to report problems in a source's data upstream.
+pub fn diagnostics_data(db: &Database, build_time: DateTime<Utc>) -> serde_json::Value {
+    let sources: Vec<_> = db
+        .named_diagnostics
+        .iter()
+        .map(|(name, diag)| {
+            let properties: serde_json::Map<_, _> = diag
+                .get_properties()
+                .into_iter()
+                .flatten()
+                .map(|(key, value)| (key.clone(), value.clone().into()))
+                .collect();
+            json!({
+                "name": name,
+                "snapshot_time": diag.get_snapshot_time(),
+                "properties": properties,
+                "entries": diag.get_entries().map_or(&[][..], |x| x),
+                "logs": diag.get_logs().map_or(&[][..], |x| x),
+            })
+        })
+        .collect();
+
+    json!({
+        "build_time": build_time,
+        "records_count": db.records.len(),
+        "sources": sources,
+    })
+}
+
+/// Coarse human-readable length of a time span, e.g. "3 days".
+fn format_age(age: TimeDelta) -> String {
+    let (count, unit) = if age.num_days() > 0 {
+        (age.num_days(), "day")
+    } else if age.num_hours() > 0 {
+        (age.num_hours(), "hour")
+    } else {
+        (age.num_minutes().max(0), "minute")
+    };
+
+    if count == 1 {
+        format!("{count} {unit}")
+    } else {
+        format!("{count} {unit}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::records::types::{Certainty, ModIdentifier, Source};
+
+    fn notice(notice: Notice) -> NoticeRecord {
+        NoticeRecord {
+            date: None,
+            notice,
+            certainty: Certainty::High,
+            source: Source::JumliDataset("Example".into()),
+            context_url: None,
+            historical: false,
+            alternative_chain: None,
+            corroborations: Vec::new(),
+        }
+    }
+
+    fn database(records: Vec<ModRecord>) -> Database {
+        let indices = records
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, record)| record.identifiers.iter().map(move |x| (x.to_string(), idx)))
+            .collect();
+        Database {
+            records,
+            indices,
+            named_diagnostics: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn summarizes_notices_of_the_alternative() {
+        let db = database(vec![
+            ModRecord {
+                id: "author.mod".into(),
+                notices: vec![notice(Notice::UseAlternative(
+                    "Other".into(),
+                    Some(1000000002),
+                    Some("Updated".into()),
+                ))],
+                identifiers: vec![ModIdentifier::PackageId("author.mod".into())],
+                recommended_instead_of: Vec::new(),
+            },
+            ModRecord {
+                id: "1000000002".into(),
+                notices: vec![notice(Notice::Unstable(Some("Crashes on load".into())))],
+                identifiers: vec![ModIdentifier::WorkshopId(1000000002)],
+                recommended_instead_of: Vec::new(),
+            },
+        ]);
+        let html = db.records[0].render_html(&SiteConfig::default(), &db);
+        assert!(html.contains("Note that the alternative has notices of its own:"));
+        assert!(
+            html.contains("<li>Unstable (JuMLi Dataset: Example)</li>"),
+            "{html}"
+        );
+        assert!(html.contains(r#"href="/mods/1000000002.html""#));
+    }
+
+    #[test]
+    fn alternative_sharing_the_record_is_not_summarized() {
+        let db = database(vec![ModRecord {
+            id: "author.mod".into(),
+            notices: vec![
+                notice(Notice::UseAlternative(
+                    "Newer Upload".into(),
+                    Some(1000000001),
+                    None,
+                )),
+                notice(Notice::Unstable(Some("Crashes on load".into()))),
+            ],
+            identifiers: vec![
+                ModIdentifier::PackageId("author.mod".into()),
+                ModIdentifier::WorkshopId(1000000001),
+            ],
+            recommended_instead_of: Vec::new(),
+        }]);
+        let html = db.records[0].render_html(&SiteConfig::default(), &db);
+        assert!(html.contains("Crashes on load"));
+        assert!(!html.contains("notices of its own"), "{html}");
+    }
+}
@@ -0,0 +1,149 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, TimeZone, Utc};
+use git2::{Commit, Repository};
+use serde::{Deserialize, Serialize};
+
+/// Raw data a source was built from, as fetched from upstream. Snapshots are cached so builds can
+/// fall back to the last good data when fetching fails.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Snapshot {
+    /// When the snapshot was fetched from upstream
+    pub fetched_at: DateTime<Utc>,
+    /// Commit the files were read from, if any
+    pub commit: Option<CommitInfo>,
+    /// Contents of the files the source reads, by name
+    pub files: BTreeMap<String, String>,
+}
+
+impl Snapshot {
+    pub fn new(commit: Option<CommitInfo>) -> Self {
+        Self {
+            fetched_at: Utc::now(),
+            commit,
+            files: BTreeMap::new(),
+        }
+    }
+}
+
+/// Commit information shown in diagnostics.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommitInfo {
+    pub id: String,
+    pub summary: Option<String>,
+    /// Seconds since the unix epoch
+    pub time: i64,
+    /// Offset of the committer's time zone
+    pub offset_minutes: i32,
+}
+
+impl CommitInfo {
+    pub fn from_commit(commit: &Commit) -> Self {
+        Self {
+            id: commit.id().to_string(),
+            summary: commit.summary().map(str::to_string),
+            time: commit.time().seconds(),
+            offset_minutes: commit.time().offset_minutes(),
+        }
+    }
+
+    pub fn from_head(repo: &Repository) -> Result<Self, git2::Error> {
+        Ok(Self::from_commit(&repo.head()?.peel_to_commit()?))
+    }
+
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        Utc.timestamp_opt(self.time, 0).single()
+    }
+}
+
+/// Cache key of a source fetched from `url`, so that snapshots of different repositories, such as
+/// a fork or a staging mirror, do not replace each other.
+pub fn remote_key(name: &str, url: &str) -> String {
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let url = url.trim_end_matches('/').trim_end_matches(".git");
+    let url: String = url
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' => c,
+            _ => '_',
+        })
+        .collect();
+    format!("{name}-{url}")
+}
+
+/// Directory storing the last good snapshot of each source.
+#[derive(Clone, Debug)]
+pub struct SnapshotCache {
+    dir: PathBuf,
+    /// Use cached snapshots without trying to fetch sources first
+    offline: bool,
+}
+
+impl SnapshotCache {
+    pub fn new(dir: PathBuf, offline: bool) -> Self {
+        Self { dir, offline }
+    }
+
+    /// `$XDG_CACHE_HOME/jumli` or `~/.cache/jumli`, falling back to the temporary directory.
+    pub fn default_dir() -> PathBuf {
+        std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|x| Path::new(&x).join(".cache")))
+            .unwrap_or_else(std::env::temp_dir)
+            .join("jumli")
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+
+    pub fn load(&self, key: &str) -> Result<Snapshot, Box<dyn Error>> {
+        let path = self.path(key);
+        let contents = std::fs::read(&path)
+            .map_err(|e| format!("Unable to read cached snapshot {path:?}: {e}"))?;
+        Ok(serde_json::from_slice(&contents)
+            .map_err(|e| format!("Unable to parse cached snapshot {path:?}: {e}"))?)
+    }
+
+    pub fn save(&self, key: &str, snapshot: &Snapshot) -> Result<(), Box<dyn Error>> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Unable to create cache directory {:?}: {e}", self.dir))?;
+
+        // Write to a temporary file first, so an interrupted build cannot leave a corrupt snapshot
+        let path = self.path(key);
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_vec(snapshot)?)
+            .map_err(|e| format!("Unable to write snapshot {temp_path:?}: {e}"))?;
+        std::fs::rename(&temp_path, &path)
+            .map_err(|e| format!("Unable to write snapshot {path:?}: {e}"))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_keys_differ_by_repository() {
+        let upstream = remote_key("jumli_data", "https://github.com/sysrqmagician/jumli");
+        assert_eq!(upstream, "jumli_data-github.com_sysrqmagician_jumli");
+        assert_eq!(
+            remote_key("jumli_data", "https://github.com/sysrqmagician/jumli.git/"),
+            upstream
+        );
+        assert_ne!(
+            remote_key("jumli_data", "https://github.com/someone/jumli"),
+            upstream
+        );
+        assert!(!remote_key("x", "file:///../../etc").contains('/'));
+    }
+}
@@ -0,0 +1,299 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    path::PathBuf,
+};
+
+use futures::future::LocalBoxFuture;
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::{
+    records::types::{IngestibleData, ModIdentifier},
+    sources::{
+        DiagnosticEntry, Diagnostics, RecordSource,
+        cache::Snapshot,
+        registry::{SourceDescriptor, SourceOption},
+        workshop_database::WorkshopDatabase,
+    },
+};
+
+/// Name of the alias file in snapshots
+const ALIASES_FILE: &str = "identity_aliases.json";
+
+/// A package id and the workshop id it is published under.
+struct Alias {
+    package_id: String,
+    workshop_id: u64,
+}
+
+/// Maps package ids to workshop ids, so records listing a mod by either are consolidated. Contributes
+/// no records of its own. Package ids published under several workshop ids, e.g. by an original mod
+/// and its continuation, are reported instead of aliased.
+pub struct IdentityAliases {
+    path: PathBuf,
+    aliases: Vec<Vec<ModIdentifier>>,
+    diagnostics: Diagnostics,
+}
+
+impl IdentityAliases {
+    pub const DESCRIPTOR: SourceDescriptor = SourceDescriptor {
+        name: "identity_aliases",
+        description: "Package id to workshop id aliases, from RimSort's Steam database or a JSON object mapping package ids to workshop ids",
+        options: &[SourceOption {
+            name: "path",
+            description: "JSON file containing the aliases",
+            default: None,
+        }],
+        enabled_by_default: false,
+        required_by_default: false,
+        create: |_, options| {
+            let path = options
+                .path("path")?
+                .ok_or("The path option is required (sources.identity_aliases.path).")?;
+            Ok(Box::new(Self::new(path)))
+        },
+    };
+
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            aliases: Vec::new(),
+            diagnostics: Diagnostics::new(),
+        }
+    }
+
+    async fn snapshot(&self) -> Result<Snapshot, Box<dyn Error>> {
+        info!("Reading identity aliases from {:?}.", self.path);
+        let contents = std::fs::read_to_string(&self.path)
+            .map_err(|e| format!("Unable to read identity aliases {:?}: {e}", self.path))?;
+
+        let mut snapshot = Snapshot::new(None);
+        snapshot.files.insert(ALIASES_FILE.into(), contents);
+        Ok(snapshot)
+    }
+
+    /// Package ids and the workshop ids they alias, along with the name of the format.
+    fn parse(contents: &str) -> Result<(&'static str, Vec<Alias>), Box<dyn Error>> {
+        let value: serde_json::Value = serde_json::from_str(contents)
+            .map_err(|e| format!("Unable to parse identity aliases: {e}"))?;
+
+        if value.get("database").is_some() {
+            let (format, mods) = WorkshopDatabase::parse(contents)?;
+            let mut aliases = Vec::new();
+            let mut invalid_ids = 0;
+            for workshop_mod in mods {
+                let Some(package_id) = workshop_mod.package_id else {
+                    continue;
+                };
+                match workshop_mod.workshop_id.trim().parse() {
+                    Ok(workshop_id) => aliases.push(Alias {
+                        package_id,
+                        workshop_id,
+                    }),
+                    Err(_) => invalid_ids += 1,
+                }
+            }
+            if invalid_ids > 0 {
+                info!("Skipped {invalid_ids} aliases with invalid workshop ids.");
+            }
+            Ok((format, aliases))
+        } else {
+            let map: BTreeMap<String, u64> = serde_json::from_value(value).map_err(|e| {
+                format!("Unable to parse identity aliases, expected package ids mapped to workshop ids: {e}")
+            })?;
+            let aliases = map
+                .into_iter()
+                .map(|(package_id, workshop_id)| Alias {
+                    package_id,
+                    workshop_id,
+                })
+                .collect();
+            Ok(("Alias map", aliases))
+        }
+    }
+}
+
+impl RecordSource for IdentityAliases {
+    fn fetch_snapshot(&mut self) -> LocalBoxFuture<'_, Result<Snapshot, Box<dyn Error>>> {
+        Box::pin(self.snapshot())
+    }
+
+    fn process(&mut self, mut snapshot: Snapshot) -> Result<(), Box<dyn Error>> {
+        let contents = snapshot
+            .files
+            .remove(ALIASES_FILE)
+            .ok_or_else(|| format!("Snapshot does not contain {ALIASES_FILE}"))?;
+        let (format, aliases) = Self::parse(&contents)?;
+
+        // Package ids are case-insensitive, spellings differing only in case are the same mod
+        let mut workshop_ids: BTreeMap<String, (String, BTreeSet<u64>)> = BTreeMap::new();
+        for Alias {
+            package_id,
+            workshop_id,
+        } in aliases
+        {
+            // Malformed identifiers would merge unrelated mods
+            if package_id.trim().is_empty() || workshop_id == 0 {
+                continue;
+            }
+            workshop_ids
+                .entry(package_id.trim().to_lowercase())
+                .or_insert_with(|| (package_id, BTreeSet::new()))
+                .1
+                .insert(workshop_id);
+        }
+
+        // A package id shared by several workshop items, e.g. an original mod and its continuation,
+        // would merge their records through it
+        let mut ambiguous_count = 0;
+        for (package_id, ids) in workshop_ids.into_values() {
+            if ids.len() > 1 {
+                ambiguous_count += 1;
+                self.diagnostics.add_entry(DiagnosticEntry {
+                    kind: "ambiguous_package_id".into(),
+                    location: package_id,
+                    message: "Package id is published under several workshop ids, not aliased"
+                        .into(),
+                    value: Some(json!(ids)),
+                });
+                continue;
+            }
+            let mut group = vec![ModIdentifier::PackageId(package_id)];
+            group.extend(ids.into_iter().map(ModIdentifier::WorkshopId));
+            self.aliases.push(group);
+        }
+
+        if ambiguous_count > 0 {
+            warn!("Skipped {ambiguous_count} package ids published under several workshop ids.");
+        }
+        info!(
+            "Completed identity alias processing, yielding {} aliases.",
+            self.aliases.len()
+        );
+        self.diagnostics.add_property("format", format);
+        self.diagnostics
+            .add_property("aliases_count", self.aliases.len().to_string());
+        self.diagnostics
+            .add_property("ambiguous_package_ids_count", ambiguous_count.to_string());
+
+        Ok(())
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        // The aliases are a local file, there is nothing to fall back to
+        None
+    }
+
+    fn get_records(&mut self) -> Option<&mut Vec<IngestibleData>> {
+        None
+    }
+
+    fn get_aliases(&mut self) -> Option<&mut Vec<Vec<ModIdentifier>>> {
+        if self.aliases.is_empty() {
+            None
+        } else {
+            Some(&mut self.aliases)
+        }
+    }
+
+    fn take_diagnostics(&mut self) -> Diagnostics {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    fn get_name(&self) -> &'static str {
+        "Identity Aliases"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(contents: &str) -> IdentityAliases {
+        let mut snapshot = Snapshot::new(None);
+        snapshot.files.insert(ALIASES_FILE.into(), contents.into());
+        let mut source = IdentityAliases::new(PathBuf::new());
+        source.process(snapshot).unwrap();
+        source
+    }
+
+    fn property(source: &IdentityAliases, key: &str) -> Option<String> {
+        source
+            .diagnostics
+            .get_properties()?
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.clone())
+    }
+
+    #[test]
+    fn parses_alias_map() {
+        let source = process(include_str!("../../fixtures/identity_aliases/aliases.json"));
+        assert_eq!(property(&source, "format").as_deref(), Some("Alias map"));
+        assert_eq!(source.aliases.len(), 4);
+        assert!(source.aliases.contains(&vec![
+            ModIdentifier::PackageId("UnlimitedHugs.AllowTool".into()),
+            ModIdentifier::WorkshopId(761421485)
+        ]));
+        assert_eq!(
+            property(&source, "ambiguous_package_ids_count").as_deref(),
+            Some("0")
+        );
+    }
+
+    #[test]
+    fn parses_rimsort_database() {
+        let source = process(include_str!(
+            "../../fixtures/workshop_database/rimsort_steam_db.json"
+        ));
+        // The item without a package id and the invalid workshop id are skipped
+        assert_eq!(
+            source.aliases,
+            [
+                vec![
+                    ModIdentifier::PackageId("brrainz.harmony".into()),
+                    ModIdentifier::WorkshopId(2009463077)
+                ],
+                vec![
+                    ModIdentifier::PackageId("example.outdated".into()),
+                    ModIdentifier::WorkshopId(1000000001)
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn ambiguous_package_ids_are_not_aliased() {
+        // An original mod and its continuation, sharing a package id in different case
+        let source = process(
+            r#"{"database": {
+                "1000000001": {"packageId": "author.mod"},
+                "2000000002": {"packageId": "Author.Mod"},
+                "3000000003": {"packageId": "author.other"}
+            }}"#,
+        );
+        assert_eq!(
+            source.aliases,
+            [vec![
+                ModIdentifier::PackageId("author.other".into()),
+                ModIdentifier::WorkshopId(3000000003)
+            ]]
+        );
+        let entries = source.diagnostics.get_entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].kind, "ambiguous_package_id");
+        assert_eq!(entries[0].value, Some(json!([1000000001, 2000000002])));
+        assert_eq!(
+            property(&source, "ambiguous_package_ids_count").as_deref(),
+            Some("1")
+        );
+    }
+
+    #[test]
+    fn invalid_identifiers_are_skipped() {
+        let source = process(r#"{"author.mod": 0, " ": 1000000001}"#);
+        assert!(source.aliases.is_empty());
+        assert!(IdentityAliases::parse(r#"{"author.mod": [1000000001]}"#).is_err());
+    }
+}
@@ -1,123 +1,255 @@
-use std::{cell::LazyCell, env::temp_dir, fs::File, io::BufReader};
+use std::{
+    error::Error,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
 
-use chrono::NaiveDate;
-use git2::FetchOptions;
-use ron::extensions::Extensions;
-use serde::{Deserialize, Serialize};
-use tokio::task::JoinSet;
+use futures::future::LocalBoxFuture;
+use git2::Repository;
 use tracing::info;
 
 use crate::{
-    records::types::{Certainty, IngestibleData, ModIdentifier, Notice, NoticeRecord, Source},
-    sources::{Diagnostics, RecordSource},
+    dataset::{DatasetFormat, RawDataset},
+    records::types::IngestibleData,
+    sources::{
+        Diagnostics, RecordSource,
+        cache::{self, CommitInfo, Snapshot},
+        clone_shallow,
+        registry::{SourceDescriptor, SourceOption, SourceOptions},
+    },
 };
 
-pub const REPOSITORY_URL: &'static str = "https://github.com/sysrqmagician/jumli";
-pub const RON_OPTIONS: LazyCell<ron::Options> =
-    LazyCell::new(|| ron::Options::default().with_default_extension(Extensions::IMPLICIT_SOME));
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(rename = "Dataset")]
-struct DatasetFile {
-    name: String,
-    description: String,
-    records: Vec<DatasetFileRecord>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct DatasetFileRecord {
-    identifiers: Vec<ModIdentifier>,
-    notices: Vec<LocalNotice>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct LocalNotice {
-    pub date: Option<NaiveDate>,
-    pub notice: Notice,
-    pub certainty: Certainty,
-    pub context_url: Option<String>,
-    #[serde(default)]
-    pub historical: bool,
-}
+pub const REPOSITORY_URL: &str = "https://github.com/sysrqmagician/jumli";
+/// Location of the datasets relative to the repository root
+pub const RECORDS_SUBDIR: &str = "jumli_data/records";
 
-impl Into<Vec<IngestibleData>> for DatasetFile {
-    fn into(self) -> Vec<IngestibleData> {
-        self.records
-            .into_iter()
-            .map(|entry| IngestibleData {
-                identifiers: entry.identifiers,
-                notices: entry
-                    .notices
-                    .into_iter()
-                    .map(|local| NoticeRecord {
-                        certainty: local.certainty,
-                        context_url: local.context_url,
-                        date: local.date,
-                        notice: local.notice,
-                        source: Source::JumliDataset(self.name.clone()),
-                        historical: local.historical,
-                    })
-                    .collect(),
-            })
-            .collect()
-    }
+/// Where the JuMLi datasets are read from.
+#[derive(Clone, Debug)]
+pub enum DataLocation {
+    /// Shallow clone of a remote repository, deleted after reading
+    Remote(String),
+    /// Local directory (usually a working copy), read as-is including uncommitted changes
+    Directory(PathBuf),
+    /// Specific revision of a local git repository, read from the object database
+    GitRevision {
+        repository: PathBuf,
+        revision: String,
+    },
 }
 
 pub struct JumliData {
+    location: DataLocation,
     records: Vec<IngestibleData>,
     diagnostics: Diagnostics,
 }
 
+impl Default for JumliData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl JumliData {
+    pub const DESCRIPTOR: SourceDescriptor = SourceDescriptor {
+        name: "jumli_data",
+        description: "Datasets maintained in the JuMLi repository",
+        options: &[
+            SourceOption {
+                name: "repository",
+                description: "Git repository to clone the datasets from, unless data_dir is set",
+                default: Some(REPOSITORY_URL),
+            },
+            SourceOption {
+                name: "data_dir",
+                description: "Root of a local JuMLi checkout to read datasets from instead",
+                default: None,
+            },
+            SourceOption {
+                name: "data_revision",
+                description: "Revision of data_dir to read instead of the working copy",
+                default: None,
+            },
+        ],
+        enabled_by_default: true,
+        // The site is of little use without our own datasets
+        required_by_default: true,
+        create: |_, options| Ok(Box::new(Self::from_options(options)?)),
+    };
+
     pub fn new() -> Self {
+        Self::with_location(DataLocation::Remote(REPOSITORY_URL.into()))
+    }
+
+    pub fn with_location(location: DataLocation) -> Self {
         Self {
+            location,
             records: Vec::new(),
             diagnostics: Diagnostics::new(),
         }
     }
-}
 
-impl RecordSource for JumliData {
-    async fn fetch(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let mut repo_fo = FetchOptions::new();
-        repo_fo.depth(1);
+    pub fn from_options(options: &SourceOptions) -> Result<Self, Box<dyn Error>> {
+        let location = match (options.path("data_dir")?, options.string("data_revision")?) {
+            (Some(root), Some(revision)) => DataLocation::GitRevision {
+                repository: root,
+                revision,
+            },
+            (Some(root), None) => DataLocation::Directory(root),
+            (None, Some(_)) => {
+                return Err("A data revision requires a data directory (--data-dir or sources.jumli_data.data_dir).".into());
+            }
+            (None, None) => DataLocation::Remote(
+                options
+                    .string("repository")?
+                    .unwrap_or_else(|| REPOSITORY_URL.into()),
+            ),
+        };
+        Ok(Self::with_location(location))
+    }
 
-        let mut repo_builder = git2::build::RepoBuilder::new();
-        repo_builder.fetch_options(repo_fo);
+    async fn fetch_remote(url: &str) -> Result<Snapshot, Box<dyn std::error::Error>> {
+        info!("Cloning JuMLi Repo {url}.");
+        let (repo_dir, repo) = clone_shallow(url.into()).await?;
+        info!("Cloned JuMLi Repo.");
 
-        let mut repo_dir = temp_dir();
-        repo_dir.push("jumli_repo");
+        let mut snapshot = Snapshot::new(CommitInfo::from_head(&repo).ok());
+        // Name datasets relative to the repository, the clone's location changes with every build
+        for (name, path) in Self::list_directory(&repo_dir.path().join(RECORDS_SUBDIR))? {
+            let name = format!("{RECORDS_SUBDIR}/{}", name.to_string_lossy());
+            snapshot.files.insert(name, Self::read_file(&path)?);
+        }
+        Ok(snapshot)
+    }
 
-        info!("Cloning JuMLi Repo to {repo_dir:?}.");
-        let repo = repo_builder.clone(REPOSITORY_URL, &repo_dir)?;
-        self.diagnostics.add_git_info(&repo);
-        info!("Cloned JuMLi Repo.");
+    fn read_directory(records_dir: &Path) -> Result<Snapshot, Box<dyn std::error::Error>> {
+        let commit = Repository::discover(records_dir)
+            .ok()
+            .and_then(|repo| CommitInfo::from_head(&repo).ok());
+
+        let mut snapshot = Snapshot::new(commit);
+        for (_, path) in Self::list_directory(records_dir)? {
+            snapshot
+                .files
+                .insert(path.display().to_string(), Self::read_file(&path)?);
+        }
+        Ok(snapshot)
+    }
+
+    /// Dataset files in a directory, by file name.
+    fn list_directory(
+        records_dir: &Path,
+    ) -> Result<Vec<(OsString, PathBuf)>, Box<dyn std::error::Error>> {
+        Ok(std::fs::read_dir(records_dir)
+            .map_err(|e| format!("Unable to read records directory {records_dir:?}: {e}"))?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry.path().is_file() && DatasetFormat::from_path(entry.path()).is_some()
+            })
+            .map(|entry| (entry.file_name(), entry.path()))
+            .collect())
+    }
 
-        let mut records_dir = repo_dir.clone();
-        records_dir.push("jumli_data/records");
+    fn read_file(path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read dataset {path:?}: {e}"))?)
+    }
 
-        let mut handles: JoinSet<Result<Vec<IngestibleData>, String>> = JoinSet::new();
+    fn read_revision(
+        repository: &Path,
+        revision: &str,
+    ) -> Result<Snapshot, Box<dyn std::error::Error>> {
+        let repo = Repository::open(repository)
+            .map_err(|e| format!("Unable to open repository {repository:?}: {e}"))?;
+        let commit = repo
+            .revparse_single(revision)
+            .and_then(|x| x.peel_to_commit())
+            .map_err(|e| format!("Unable to resolve revision {revision:?}: {e}"))?;
 
-        let mut read_dir = std::fs::read_dir(&records_dir)?;
-        while let Some(Ok(entry)) = read_dir.next() {
-            handles.spawn(async move {
-                let reader = BufReader::new(
-                    File::open(entry.path())
-                        .map_err(|e| format!("Unable to read dataset {:?}: {e}", entry.path()))?,
-                );
+        let records_tree = commit
+            .tree()?
+            .get_path(Path::new(RECORDS_SUBDIR))
+            .and_then(|x| x.to_object(&repo))
+            .and_then(|x| x.peel_to_tree())
+            .map_err(|e| format!("Unable to find {RECORDS_SUBDIR} at {revision:?}: {e}"))?;
 
-                let dataset: DatasetFile = RON_OPTIONS
-                    .from_reader(reader)
-                    .map_err(|e| format!("Unable to parse dataset {:?}: {e}", entry.path()))?;
+        let mut snapshot = Snapshot::new(Some(CommitInfo::from_commit(&commit)));
+        for entry in records_tree.iter() {
+            let name = format!(
+                "{revision}:{RECORDS_SUBDIR}/{}",
+                entry.name().unwrap_or("<non-utf8 name>")
+            );
+            if DatasetFormat::from_path(&name).is_none() {
+                continue;
+            }
+            let Ok(blob) = entry.to_object(&repo).and_then(|x| x.peel_to_blob()) else {
+                continue;
+            };
 
-                Ok(dataset.into())
-            });
+            let contents = String::from_utf8(blob.content().to_vec())
+                .map_err(|e| format!("Unable to read dataset {name:?}: {e}"))?;
+            snapshot.files.insert(name, contents);
         }
 
-        while let Some(Ok(result)) = handles.join_next().await {
-            match result {
-                Ok(mut record) => self.records.append(&mut record),
-                Err(error) => self.diagnostics.log(error),
+        Ok(snapshot)
+    }
+
+    async fn snapshot(&self) -> Result<Snapshot, Box<dyn std::error::Error>> {
+        match &self.location {
+            DataLocation::Remote(url) => Self::fetch_remote(url).await,
+            DataLocation::Directory(root) => {
+                info!("Reading JuMLi data from {root:?}.");
+                Self::read_directory(&root.join(RECORDS_SUBDIR))
+            }
+            DataLocation::GitRevision {
+                repository,
+                revision,
+            } => {
+                info!("Reading JuMLi data from {repository:?} at {revision}.");
+                Self::read_revision(repository, revision)
+            }
+        }
+    }
+
+    /// Read the unparsed dataset files from this source's location.
+    pub async fn read_datasets(&self) -> Result<Vec<RawDataset>, Box<dyn std::error::Error>> {
+        Ok(datasets(self.snapshot().await?))
+    }
+}
+
+/// Dataset files of a snapshot, sorted by name.
+fn datasets(snapshot: Snapshot) -> Vec<RawDataset> {
+    snapshot
+        .files
+        .into_iter()
+        .filter_map(|(name, contents)| {
+            Some(RawDataset {
+                format: DatasetFormat::from_path(&name)?,
+                name,
+                contents,
+            })
+        })
+        .collect()
+}
+
+impl RecordSource for JumliData {
+    fn fetch_snapshot(&mut self) -> LocalBoxFuture<'_, Result<Snapshot, Box<dyn Error>>> {
+        Box::pin(self.snapshot())
+    }
+
+    fn process(&mut self, snapshot: Snapshot) -> Result<(), Box<dyn std::error::Error>> {
+        if let DataLocation::Directory(_) = self.location
+            && snapshot.commit.is_some()
+        {
+            self.diagnostics
+                .log("Read from a local working copy, which may differ from the commit.");
+        }
+
+        for raw in datasets(snapshot) {
+            match raw.parse() {
+                Ok(dataset) => self.records.append(&mut dataset.into()),
+                Err(e) => self
+                    .diagnostics
+                    .log_error(format!("Unable to parse dataset {:?}: {e}", raw.name)),
             }
         }
 
@@ -128,12 +260,17 @@ impl RecordSource for JumliData {
         self.diagnostics
             .add_property("raw_records_count", self.records.len().to_string());
 
-        info!("Deleting JuMLi Repo {repo_dir:?}.",);
-
-        std::fs::remove_dir_all(repo_dir)?;
         Ok(())
     }
 
+    fn cache_key(&self) -> Option<String> {
+        // Local data is always available, so only remote data is worth caching
+        match &self.location {
+            DataLocation::Remote(url) => Some(cache::remote_key("jumli_data", url)),
+            _ => None,
+        }
+    }
+
     fn get_records(&mut self) -> Option<&mut Vec<IngestibleData>> {
         if self.records.is_empty() {
             None
@@ -142,8 +279,8 @@ impl RecordSource for JumliData {
         }
     }
 
-    fn get_diagnostics(self) -> Diagnostics {
-        self.diagnostics
+    fn take_diagnostics(&mut self) -> Diagnostics {
+        std::mem::take(&mut self.diagnostics)
     }
 
     fn get_name(&self) -> &'static str {
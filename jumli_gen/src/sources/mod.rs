@@ -1,12 +1,36 @@
 use std::error::Error;
 
-use chrono::{TimeZone, Utc};
-use git2::Repository;
+use chrono::{DateTime, TimeZone, Utc};
+use futures::future::LocalBoxFuture;
+use git2::{FetchOptions, Repository, build::RepoBuilder};
+use serde::Serialize;
+use tempfile::TempDir;
 
-use crate::records::types::IngestibleData;
+use crate::{
+    records::types::{IngestibleData, ModIdentifier},
+    sources::cache::{CommitInfo, Snapshot},
+};
 
+pub mod cache;
+pub mod identity_aliases;
 pub mod jumli_data;
+pub mod mods_folder;
+pub mod registry;
 pub mod use_this_instead;
+pub mod workshop_database;
+
+/// A problem with a single item of a source's input, such as a malformed rule, listed in the
+/// diagnostics so it can be reported upstream.
+#[derive(Serialize, Debug, Clone)]
+pub struct DiagnosticEntry {
+    /// Short machine-readable kind of problem, e.g. `zero_workshop_id`
+    pub kind: String,
+    /// Where in the input the problem was found, e.g. `rules[12].oldWorkshopId`
+    pub location: String,
+    pub message: String,
+    /// The offending value as found in the input
+    pub value: Option<serde_json::Value>,
+}
 
 /// Diagnostics to be shown on /status.html. Note all of this information will be public.
 #[derive(Default)]
@@ -15,6 +39,14 @@ pub struct Diagnostics {
     properties: Option<Vec<(String, String)>>,
     /// Non-fatal errors and misc. info
     log_lines: Option<Vec<String>>,
+    /// Number of log lines that describe errors
+    error_count: usize,
+    /// Problems with individual input items
+    entries: Option<Vec<DiagnosticEntry>>,
+    /// Time of the commit the data was read from
+    commit_time: Option<DateTime<Utc>>,
+    /// Fetch time of the cached snapshot used instead of fresh data
+    snapshot_time: Option<DateTime<Utc>>,
 }
 
 impl Diagnostics {
@@ -22,6 +54,10 @@ impl Diagnostics {
         Self {
             properties: None,
             log_lines: None,
+            error_count: 0,
+            entries: None,
+            commit_time: None,
+            snapshot_time: None,
         }
     }
 
@@ -29,6 +65,16 @@ impl Diagnostics {
         self.log_lines.get_or_insert_default().push(message.into());
     }
 
+    /// Log a non-fatal error, counted towards `has_errors`.
+    pub fn log_error(&mut self, message: impl Into<String>) {
+        self.error_count += 1;
+        self.log(message);
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.error_count > 0
+    }
+
     pub fn add_property(&mut self, key: impl Into<String>, value: impl Into<String>) {
         self.properties
             .get_or_insert_default()
@@ -39,50 +85,88 @@ impl Diagnostics {
         self.properties.as_ref()
     }
 
+    pub fn add_entry(&mut self, entry: DiagnosticEntry) {
+        self.entries.get_or_insert_default().push(entry);
+    }
+
+    pub fn get_entries(&self) -> Option<&Vec<DiagnosticEntry>> {
+        self.entries.as_ref()
+    }
+
     pub fn get_logs(&self) -> Option<&Vec<String>> {
         self.log_lines.as_ref()
     }
 
-    pub fn add_git_info(&mut self, repo: &Repository) {
-        let latest_commit = repo.head().and_then(|x| x.peel_to_commit());
-        match latest_commit {
-            Ok(latest_commit) => {
-                self.add_property("git_commit", latest_commit.id().to_string());
-                self.add_property(
-                    "git_commit_summary",
-                    latest_commit.summary().unwrap_or("Failed to retrieve."),
-                );
-
-                let timestamp = Utc
-                    .timestamp_opt(
-                        latest_commit.time().seconds()
-                            + i64::from(latest_commit.time().offset_minutes() * 60),
-                        0,
-                    )
-                    .latest()
-                    .and_then(|x| Some(x.to_rfc3339()));
-
-                self.add_property(
-                    "git_commit_time",
-                    timestamp.unwrap_or("Failed to retrieve.".into()),
-                );
-            }
-            Err(e) => {
-                self.log(format!(
-                    "Failed to retrieve git commit information for diagnostics: {e}"
-                ));
-            }
-        }
+    pub fn get_commit_time(&self) -> Option<DateTime<Utc>> {
+        self.commit_time
+    }
+
+    pub fn add_commit_info(&mut self, commit: &CommitInfo) {
+        self.commit_time = commit.time();
+        self.add_property("git_commit", commit.id.clone());
+        self.add_property(
+            "git_commit_summary",
+            commit.summary.as_deref().unwrap_or("Failed to retrieve."),
+        );
+
+        let timestamp = Utc
+            .timestamp_opt(commit.time + i64::from(commit.offset_minutes * 60), 0)
+            .latest()
+            .map(|x| x.to_rfc3339());
+
+        self.add_property(
+            "git_commit_time",
+            timestamp.unwrap_or("Failed to retrieve.".into()),
+        );
+    }
+
+    pub fn get_snapshot_time(&self) -> Option<DateTime<Utc>> {
+        self.snapshot_time
+    }
+
+    /// Record that a cached snapshot fetched at `time` was used.
+    pub fn set_snapshot_time(&mut self, time: DateTime<Utc>) {
+        self.snapshot_time = Some(time);
     }
 }
 
+/// Shallow-clone a repository into a new temporary directory, which is deleted when dropped. The
+/// clone runs without blocking the runtime, so other sources can be fetched and timeouts can fire in
+/// the meantime.
+pub async fn clone_shallow(url: String) -> Result<(TempDir, Repository), Box<dyn Error>> {
+    let dir = tempfile::Builder::new().prefix("jumli_").tempdir()?;
+    let (dir, repo) = tokio::task::spawn_blocking(move || {
+        let mut fetch_options = FetchOptions::new();
+        fetch_options.depth(1);
+        let repo = RepoBuilder::new()
+            .fetch_options(fetch_options)
+            .clone(&url, dir.path())
+            .map_err(|e| format!("Unable to clone {url}: {e}"))?;
+        Ok::<_, String>((dir, repo))
+    })
+    .await??;
+
+    Ok((dir, repo))
+}
+
+/// A source of mod records. Sources are used as trait objects, see `registry` for how they are
+/// made available to the build.
 pub trait RecordSource {
-    /// Fetch raw data and process it for later retrieval as IngestibleData using get_records
-    fn fetch(&mut self) -> impl Future<Output = Result<(), Box<dyn Error>>>;
+    /// Fetch the raw data this source is built from
+    fn fetch_snapshot(&mut self) -> LocalBoxFuture<'_, Result<Snapshot, Box<dyn Error>>>;
+    /// Process a snapshot, either freshly fetched or cached, for later retrieval as IngestibleData
+    /// using get_records
+    fn process(&mut self, snapshot: Snapshot) -> Result<(), Box<dyn Error>>;
+    /// Name the source's snapshots are cached under, `None` if they should not be cached
+    fn cache_key(&self) -> Option<String>;
     /// Get successfully parsed data
     fn get_records(&mut self) -> Option<&mut Vec<IngestibleData>>;
-    /// Get info for status.html
-    fn get_diagnostics(self) -> Diagnostics;
+    /// Get groups of identifiers known to refer to the same mod, used to consolidate records
+    fn get_aliases(&mut self) -> Option<&mut Vec<Vec<ModIdentifier>>> {
+        None
+    }
+    /// Take info for status.html, leaving empty diagnostics behind
+    fn take_diagnostics(&mut self) -> Diagnostics;
     /// Get Source name
     fn get_name(&self) -> &'static str;
 }
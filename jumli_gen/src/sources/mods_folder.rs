@@ -0,0 +1,587 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use futures::future::LocalBoxFuture;
+use serde::Deserialize;
+use tracing::info;
+
+use crate::{
+    modlist::{ModlistEntry, parse_workshop_url},
+    records::types::{Certainty, IngestibleData, ModIdentifier, Notice, NoticeRecord, Source},
+    sources::{
+        Diagnostics, RecordSource,
+        cache::Snapshot,
+        registry::{SourceDescriptor, SourceOption},
+    },
+};
+
+const ABOUT_FILE: &str = "About/About.xml";
+/// Written by Steam for workshop mods and by the game when uploading
+const PUBLISHED_FILE_ID_FILE: &str = "About/PublishedFileId.txt";
+
+/// Contents of `About/About.xml`; other elements are ignored.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AboutXml {
+    name: Option<String>,
+    author: Option<String>,
+    authors: Option<XmlList<String>>,
+    package_id: Option<String>,
+    supported_versions: Option<XmlList<String>>,
+    mod_dependencies: Option<XmlList<XmlDependency>>,
+    /// Dependencies by release, keyed like `v1.5`
+    mod_dependencies_by_version: Option<BTreeMap<String, XmlList<XmlDependency>>>,
+    incompatible_with: Option<XmlList<String>>,
+    load_before: Option<XmlList<String>>,
+    load_after: Option<XmlList<String>>,
+}
+
+/// A list of `<li>` elements, as used throughout RimWorld's XML files.
+#[derive(Deserialize)]
+pub struct XmlList<T> {
+    #[serde(default = "Vec::new")]
+    pub li: Vec<T>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct XmlDependency {
+    package_id: String,
+    display_name: Option<String>,
+    steam_workshop_url: Option<String>,
+}
+
+/// Metadata of an installed mod.
+#[derive(Debug, Clone)]
+pub struct ModMetadata {
+    /// Name of the mod's folder
+    pub folder: String,
+    pub name: String,
+    pub authors: Vec<String>,
+    pub package_id: String,
+    pub workshop_id: Option<u64>,
+    pub supported_versions: Vec<String>,
+    pub dependencies: Vec<Dependency>,
+    pub incompatible_with: Vec<String>,
+    pub load_before: Vec<String>,
+    pub load_after: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    pub package_id: String,
+    pub name: Option<String>,
+    pub workshop_id: Option<u64>,
+}
+
+impl ModMetadata {
+    /// Parse a mod's `About.xml`. Dependencies are taken for `release` if the mod declares
+    /// dependencies by version.
+    pub fn parse(
+        folder: &str,
+        about_xml: &str,
+        published_file_id: Option<&str>,
+        release: &str,
+    ) -> Result<Self, Box<dyn Error>> {
+        let about: AboutXml =
+            quick_xml::de::from_str(about_xml.strip_prefix('\u{feff}').unwrap_or(about_xml))
+                .map_err(|e| format!("Unable to parse {folder}/{ABOUT_FILE}: {e}"))?;
+
+        let package_id = about
+            .package_id
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+            .ok_or_else(|| format!("{folder}/{ABOUT_FILE} does not contain a packageId"))?;
+        // Workshop content folders are named after the workshop id
+        let workshop_id = published_file_id
+            .and_then(|x| x.trim().parse().ok())
+            .or_else(|| folder.parse().ok());
+
+        let mut authors: Vec<String> = about.author.into_iter().collect();
+        authors.extend(about.authors.map(|x| x.li).unwrap_or_default());
+
+        let mut dependencies_by_version = about.mod_dependencies_by_version.unwrap_or_default();
+        let dependencies = dependencies_by_version
+            .remove(&format!("v{release}"))
+            .or(about.mod_dependencies)
+            .map(|x| x.li)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|x| Dependency {
+                package_id: x.package_id.trim().to_string(),
+                name: x.display_name,
+                workshop_id: x
+                    .steam_workshop_url
+                    .as_deref()
+                    .and_then(workshop_id_from_url),
+            })
+            .collect();
+
+        let list = |list: Option<XmlList<String>>| -> Vec<String> {
+            list.map(|x| x.li.into_iter().map(|x| x.trim().to_string()).collect())
+                .unwrap_or_default()
+        };
+
+        Ok(Self {
+            folder: folder.into(),
+            name: about.name.unwrap_or_else(|| package_id.clone()),
+            authors,
+            package_id,
+            workshop_id,
+            supported_versions: list(about.supported_versions),
+            dependencies,
+            incompatible_with: list(about.incompatible_with),
+            load_before: list(about.load_before),
+            load_after: list(about.load_after),
+        })
+    }
+
+    pub fn identifiers(&self) -> Vec<ModIdentifier> {
+        let mut identifiers = vec![ModIdentifier::PackageId(self.package_id.clone())];
+        identifiers.extend(self.workshop_id.map(ModIdentifier::WorkshopId));
+        identifiers
+    }
+}
+
+impl From<&ModMetadata> for ModlistEntry {
+    fn from(value: &ModMetadata) -> Self {
+        Self {
+            name: value.name.clone(),
+            identifiers: value.identifiers(),
+            position: None,
+        }
+    }
+}
+
+/// Workshop id of a `steam://url/CommunityFilePage/<id>` or web URL.
+fn workshop_id_from_url(url: &str) -> Option<u64> {
+    url.trim()
+        .strip_prefix("steam://url/CommunityFilePage/")
+        .and_then(|x| x.trim_end_matches('/').parse().ok())
+        .or_else(|| parse_workshop_url(url))
+}
+
+/// Whether the rules of either mod require `a` to be loaded before `b`.
+fn loads_before(a: &ModMetadata, b: &ModMetadata) -> bool {
+    a.load_before
+        .iter()
+        .any(|x| x.eq_ignore_ascii_case(&b.package_id))
+        || b.load_after
+            .iter()
+            .any(|x| x.eq_ignore_ascii_case(&a.package_id))
+}
+
+/// Official expansions are installed with the game rather than into the Mods folder.
+fn is_expansion(package_id: &str) -> bool {
+    package_id.to_lowercase().starts_with("ludeon.rimworld")
+}
+
+/// Mods installed in a RimWorld `Mods` folder or a workshop content directory.
+pub struct ModsFolder {
+    path: PathBuf,
+    latest_release: String,
+    records: Vec<IngestibleData>,
+    diagnostics: Diagnostics,
+}
+
+impl ModsFolder {
+    pub const DESCRIPTOR: SourceDescriptor = SourceDescriptor {
+        name: "mods_folder",
+        description: "Out of date mods, missing dependencies and conflicts among the mods installed in a local Mods or workshop content folder",
+        options: &[SourceOption {
+            name: "path",
+            description: "Folder containing one folder per mod",
+            default: None,
+        }],
+        enabled_by_default: false,
+        required_by_default: false,
+        create: |config, options| {
+            let path = options
+                .path("path")?
+                .ok_or("The path option is required (sources.mods_folder.path).")?;
+            Ok(Box::new(Self::new(
+                path,
+                config.latest_rimworld_release.clone(),
+            )))
+        },
+    };
+
+    pub fn new(path: PathBuf, latest_release: String) -> Self {
+        Self {
+            path,
+            latest_release,
+            records: Vec::new(),
+            diagnostics: Diagnostics::new(),
+        }
+    }
+
+    /// Read the metadata files of all mods in `path`, keyed by `<folder>/About/<file>`.
+    pub fn read_folder(path: &Path) -> Result<Snapshot, Box<dyn Error>> {
+        let mut snapshot = Snapshot::new(None);
+        let entries = std::fs::read_dir(path)
+            .map_err(|e| format!("Unable to read mods folder {path:?}: {e}"))?;
+        for entry in entries.filter_map(|x| x.ok()) {
+            if !entry.path().is_dir() {
+                continue;
+            }
+            let folder = entry.file_name().to_string_lossy().into_owned();
+            for file in [ABOUT_FILE, PUBLISHED_FILE_ID_FILE] {
+                let Some(file_path) = find_case_insensitive(&entry.path(), file) else {
+                    continue;
+                };
+                let contents = std::fs::read_to_string(&file_path)
+                    .map_err(|e| format!("Unable to read {file_path:?}: {e}"))?;
+                snapshot.files.insert(format!("{folder}/{file}"), contents);
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// Parse the mods of a snapshot created by `read_folder`, sorted by folder. Mods that fail to
+    /// parse are returned as errors.
+    pub fn parse_snapshot(snapshot: &Snapshot, release: &str) -> (Vec<ModMetadata>, Vec<String>) {
+        let (mut mods, mut errors) = (Vec::new(), Vec::new());
+        for (key, about_xml) in &snapshot.files {
+            let Some(folder) = key.strip_suffix(&format!("/{ABOUT_FILE}")) else {
+                continue;
+            };
+            let published_file_id = snapshot
+                .files
+                .get(&format!("{folder}/{PUBLISHED_FILE_ID_FILE}"))
+                .map(String::as_str);
+            match ModMetadata::parse(folder, about_xml, published_file_id, release) {
+                Ok(metadata) => mods.push(metadata),
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+        (mods, errors)
+    }
+
+    /// Notices about each installed mod, by index into `mods`.
+    pub fn analyze(mods: &[ModMetadata], latest_release: &str) -> Vec<Vec<Notice>> {
+        // Package ids are case-insensitive in game
+        let installed: HashMap<String, &ModMetadata> = mods
+            .iter()
+            .map(|x| (x.package_id.to_lowercase(), x))
+            .collect();
+        let name_of = |package_id: &str| {
+            installed
+                .get(&package_id.to_lowercase())
+                .map(|x| format!("{} ({package_id})", x.name))
+                .unwrap_or_else(|| package_id.to_string())
+        };
+
+        mods.iter()
+            .map(|metadata| {
+                let mut notices = Vec::new();
+                if !metadata.supported_versions.is_empty()
+                    && !metadata
+                        .supported_versions
+                        .iter()
+                        .any(|x| x == latest_release)
+                {
+                    notices.push(Notice::OutOfDate);
+                }
+
+                for dependency in &metadata.dependencies {
+                    if is_expansion(&dependency.package_id)
+                        || installed.contains_key(&dependency.package_id.to_lowercase())
+                    {
+                        continue;
+                    }
+                    let name = match &dependency.name {
+                        Some(name) => format!("{name} ({})", dependency.package_id),
+                        None => dependency.package_id.clone(),
+                    };
+                    let link = dependency
+                        .workshop_id
+                        .map(|id| {
+                            format!(
+                                " Get it from https://steamcommunity.com/sharedfiles/filedetails/?id={id}"
+                            )
+                        })
+                        .unwrap_or_default();
+                    notices.push(Notice::Miscellaneous(format!(
+                        "Requires {name}, which is not installed.{link}"
+                    )));
+                }
+
+                for incompatible in &metadata.incompatible_with {
+                    if installed.contains_key(&incompatible.to_lowercase()) {
+                        notices.push(Notice::Miscellaneous(format!(
+                            "Incompatible with {}, which is installed.",
+                            name_of(incompatible)
+                        )));
+                    }
+                }
+
+                // Rules of both mods are followed by the game, contradicting ones cannot be satisfied
+                let mut others: Vec<&String> =
+                    metadata.load_before.iter().chain(&metadata.load_after).collect();
+                others.sort_by_key(|x| x.to_lowercase());
+                others.dedup_by_key(|x| x.to_lowercase());
+                for other_id in others {
+                    let Some(other) = installed.get(&other_id.to_lowercase()) else {
+                        continue;
+                    };
+                    if loads_before(metadata, other) && loads_before(other, metadata) {
+                        notices.push(Notice::Miscellaneous(format!(
+                            "Has contradicting load order rules with {}.",
+                            name_of(other_id)
+                        )));
+                    }
+                }
+
+                notices
+            })
+            .collect()
+    }
+
+    async fn snapshot(&self) -> Result<Snapshot, Box<dyn Error>> {
+        info!("Reading mods from {:?}.", self.path);
+        Self::read_folder(&self.path)
+    }
+}
+
+/// Find a relative path below `dir`, ignoring the case of each component since mods are often
+/// packaged on case-insensitive file systems.
+fn find_case_insensitive(dir: &Path, relative: &str) -> Option<PathBuf> {
+    let exact = dir.join(relative);
+    if exact.exists() {
+        return Some(exact);
+    }
+
+    let mut path = dir.to_path_buf();
+    for component in relative.split('/') {
+        path = std::fs::read_dir(&path)
+            .ok()?
+            .filter_map(|x| x.ok())
+            .find(|x| {
+                x.file_name()
+                    .to_string_lossy()
+                    .eq_ignore_ascii_case(component)
+            })?
+            .path();
+    }
+    Some(path)
+}
+
+impl RecordSource for ModsFolder {
+    fn fetch_snapshot(&mut self) -> LocalBoxFuture<'_, Result<Snapshot, Box<dyn Error>>> {
+        Box::pin(self.snapshot())
+    }
+
+    fn process(&mut self, snapshot: Snapshot) -> Result<(), Box<dyn Error>> {
+        let (mods, errors) = Self::parse_snapshot(&snapshot, &self.latest_release);
+        for e in errors {
+            self.diagnostics.log_error(e);
+        }
+
+        let notices = Self::analyze(&mods, &self.latest_release);
+        for (metadata, notices) in mods.iter().zip(notices) {
+            if notices.is_empty() {
+                continue;
+            }
+            self.records.push(IngestibleData {
+                identifiers: metadata.identifiers(),
+                notices: notices
+                    .into_iter()
+                    .map(|notice| NoticeRecord {
+                        notice,
+                        date: None,
+                        certainty: Certainty::High,
+                        source: Source::ModMetadata,
+                        context_url: None,
+                        historical: false,
+                        alternative_chain: None,
+                        corroborations: Vec::new(),
+                    })
+                    .collect(),
+            });
+        }
+
+        info!(
+            "Completed mods folder processing, yielding {} records.",
+            self.records.len()
+        );
+        self.diagnostics
+            .add_property("mods_count", mods.len().to_string());
+        self.diagnostics
+            .add_property("raw_records_count", self.records.len().to_string());
+
+        Ok(())
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        // The mods are local, there is nothing to fall back to
+        None
+    }
+
+    fn get_records(&mut self) -> Option<&mut Vec<IngestibleData>> {
+        if self.records.is_empty() {
+            None
+        } else {
+            Some(&mut self.records)
+        }
+    }
+
+    fn take_diagnostics(&mut self) -> Diagnostics {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    fn get_name(&self) -> &'static str {
+        "Mods Folder"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/mods_folder");
+
+    fn fixture_mods() -> (Vec<ModMetadata>, Vec<String>) {
+        let snapshot = ModsFolder::read_folder(Path::new(FIXTURES)).unwrap();
+        ModsFolder::parse_snapshot(&snapshot, "1.6")
+    }
+
+    #[test]
+    fn parses_about_xml() {
+        let metadata = ModMetadata::parse(
+            "OutdatedExample",
+            include_str!("../../fixtures/mods_folder/OutdatedExample/About/About.xml"),
+            Some("1000000001\n"),
+            "1.6",
+        )
+        .unwrap();
+        assert_eq!(metadata.package_id, "example.outdated");
+        assert_eq!(metadata.name, "Outdated Example");
+        assert_eq!(metadata.authors, ["Example Author", "Another Author"]);
+        assert_eq!(metadata.workshop_id, Some(1000000001));
+        assert_eq!(metadata.supported_versions, ["1.4", "1.5"]);
+        let dependencies: Vec<_> = metadata
+            .dependencies
+            .iter()
+            .map(|x| (x.package_id.as_str(), x.name.as_deref(), x.workshop_id))
+            .collect();
+        assert_eq!(
+            dependencies,
+            [
+                ("brrainz.harmony", Some("Harmony"), Some(2009463077)),
+                ("example.missing", Some("Missing Example"), Some(1000000002)),
+            ]
+        );
+        assert_eq!(metadata.load_before, ["Example.Conflicting"]);
+    }
+
+    #[test]
+    fn parses_dependencies_by_version_and_rules() {
+        let about_xml =
+            include_str!("../../fixtures/mods_folder/ConflictingExample/about/about.xml");
+        let metadata = ModMetadata::parse("ConflictingExample", about_xml, None, "1.6").unwrap();
+        assert_eq!(metadata.authors, ["Example Author"]);
+        assert_eq!(metadata.workshop_id, None);
+        let dependencies: Vec<_> = metadata
+            .dependencies
+            .iter()
+            .map(|x| &x.package_id)
+            .collect();
+        assert_eq!(dependencies, ["ludeon.rimworld.biotech"]);
+        assert_eq!(metadata.incompatible_with, ["example.outdated"]);
+        assert_eq!(metadata.load_before, ["example.outdated"]);
+        assert_eq!(metadata.load_after, ["brrainz.harmony"]);
+
+        // Releases without dependencies of their own use the general ones
+        let metadata = ModMetadata::parse("ConflictingExample", about_xml, None, "1.5").unwrap();
+        let dependencies: Vec<_> = metadata
+            .dependencies
+            .iter()
+            .map(|x| &x.package_id)
+            .collect();
+        assert_eq!(dependencies, ["example.only.before.1.6"]);
+    }
+
+    #[test]
+    fn workshop_id_from_folder_name() {
+        let metadata = ModMetadata::parse(
+            "2009463077",
+            include_str!("../../fixtures/mods_folder/2009463077/About/About.xml"),
+            None,
+            "1.6",
+        )
+        .unwrap();
+        assert_eq!(metadata.workshop_id, Some(2009463077));
+        assert_eq!(
+            metadata.identifiers(),
+            [
+                ModIdentifier::PackageId("brrainz.harmony".into()),
+                ModIdentifier::WorkshopId(2009463077)
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_package_id_is_an_error() {
+        let error = ModMetadata::parse(
+            "Broken",
+            include_str!("../../fixtures/mods_folder/Broken/About/About.xml"),
+            None,
+            "1.6",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Broken/About/About.xml does not contain a packageId"
+        );
+    }
+
+    #[test]
+    fn reads_folders_case_insensitively() {
+        let (mods, errors) = fixture_mods();
+        let folders: Vec<_> = mods.iter().map(|x| x.folder.as_str()).collect();
+        assert_eq!(
+            folders,
+            ["2009463077", "ConflictingExample", "OutdatedExample"]
+        );
+        assert_eq!(
+            errors,
+            ["Broken/About/About.xml does not contain a packageId"]
+        );
+    }
+
+    #[test]
+    fn analyzes_installed_mods() {
+        let (mods, _) = fixture_mods();
+        let notices = ModsFolder::analyze(&mods, "1.6");
+        assert_eq!(
+            notices,
+            [
+                vec![],
+                vec![
+                    Notice::Miscellaneous(
+                        "Incompatible with Outdated Example (example.outdated), which is installed."
+                            .into()
+                    ),
+                    Notice::Miscellaneous(
+                        "Has contradicting load order rules with Outdated Example (example.outdated)."
+                            .into()
+                    ),
+                ],
+                vec![
+                    Notice::OutOfDate,
+                    Notice::Miscellaneous(
+                        "Requires Missing Example (example.missing), which is not installed. Get it from https://steamcommunity.com/sharedfiles/filedetails/?id=1000000002"
+                            .into()
+                    ),
+                    Notice::Miscellaneous(
+                        "Has contradicting load order rules with Conflicting Example (Example.Conflicting)."
+                            .into()
+                    ),
+                ],
+            ]
+        );
+    }
+}
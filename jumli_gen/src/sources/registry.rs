@@ -0,0 +1,163 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::SiteConfig,
+    records::SourcePolicy,
+    sources::{
+        RecordSource, identity_aliases::IdentityAliases, jumli_data::JumliData,
+        mods_folder::ModsFolder, use_this_instead::UseThisInstead,
+        workshop_database::WorkshopDatabase,
+    },
+};
+
+/// Creates a source from the site configuration and its options.
+pub type SourceConstructor =
+    fn(&SiteConfig, &SourceOptions) -> Result<Box<dyn RecordSource>, Box<dyn Error>>;
+
+/// A source that can be enabled by name from the command line or config.
+pub struct SourceDescriptor {
+    /// Name used on the command line and in the config
+    pub name: &'static str,
+    pub description: &'static str,
+    pub options: &'static [SourceOption],
+    pub enabled_by_default: bool,
+    /// Whether the build fails if the source cannot be fetched, unless configured otherwise
+    pub required_by_default: bool,
+    pub create: SourceConstructor,
+}
+
+/// An option accepted by a source.
+pub struct SourceOption {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Default value, shown in the source listing
+    pub default: Option<&'static str>,
+}
+
+/// Option values for a source, from the config and command line.
+#[derive(Clone, Debug, Default)]
+pub struct SourceOptions {
+    /// Values by name, with the directory relative paths in them are resolved against
+    values: BTreeMap<String, (toml::Value, Option<PathBuf>)>,
+}
+
+impl SourceOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set an option, replacing any previous value. Relative paths are resolved against `base_dir`,
+    /// or the working directory if it is `None`.
+    pub fn set(&mut self, name: impl Into<String>, value: toml::Value, base_dir: Option<&Path>) {
+        self.values
+            .insert(name.into(), (value, base_dir.map(Path::to_path_buf)));
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.values.remove(name);
+    }
+
+    pub fn string(&self, name: &str) -> Result<Option<String>, Box<dyn Error>> {
+        match self.values.get(name) {
+            None => Ok(None),
+            Some((toml::Value::String(value), _)) => Ok(Some(value.clone())),
+            Some((value, _)) => Err(format!("Option {name} must be a string, got {value}").into()),
+        }
+    }
+
+    pub fn path(&self, name: &str) -> Result<Option<PathBuf>, Box<dyn Error>> {
+        let base_dir = self.values.get(name).and_then(|(_, x)| x.as_deref());
+        Ok(self.string(name)?.map(|path| match base_dir {
+            Some(base_dir) => base_dir.join(path),
+            None => PathBuf::from(path),
+        }))
+    }
+}
+
+/// All sources available to the build.
+pub struct SourceRegistry {
+    sources: Vec<&'static SourceDescriptor>,
+}
+
+impl SourceRegistry {
+    /// Registry containing the sources shipped with jumli_gen. Sources are fetched in this order
+    /// and their records ingested in it, which keeps the output reproducible.
+    pub fn builtin() -> Self {
+        let mut registry = Self {
+            sources: Vec::new(),
+        };
+        registry.register(&UseThisInstead::DESCRIPTOR);
+        registry.register(&JumliData::DESCRIPTOR);
+        registry.register(&WorkshopDatabase::DESCRIPTOR);
+        registry.register(&IdentityAliases::DESCRIPTOR);
+        registry.register(&ModsFolder::DESCRIPTOR);
+        registry
+    }
+
+    pub fn register(&mut self, descriptor: &'static SourceDescriptor) {
+        self.sources.push(descriptor);
+    }
+
+    pub fn get(&self, name: &str) -> Result<&'static SourceDescriptor, Box<dyn Error>> {
+        let name = normalize_name(name);
+        self.sources
+            .iter()
+            .find(|x| x.name == name)
+            .copied()
+            .ok_or_else(|| {
+                format!(
+                    "Unknown source {name:?}, available sources: {}",
+                    self.names().join(", ")
+                )
+                .into()
+            })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'static SourceDescriptor> {
+        self.sources.iter().copied()
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        self.sources.iter().map(|x| x.name).collect()
+    }
+}
+
+impl SourceDescriptor {
+    /// Create the source, rejecting options it does not declare.
+    pub fn create(
+        &self,
+        config: &SiteConfig,
+        options: &SourceOptions,
+    ) -> Result<Box<dyn RecordSource>, Box<dyn Error>> {
+        for name in options.values.keys() {
+            if !self.options.iter().any(|x| x.name == name) {
+                let available: Vec<_> = self.options.iter().map(|x| x.name).collect();
+                return Err(format!(
+                    "Unknown option {name:?} for source {}, available options: {}",
+                    self.name,
+                    available.join(", ")
+                )
+                .into());
+            }
+        }
+
+        (self.create)(config, options)
+    }
+
+    pub fn default_policy(&self) -> SourcePolicy {
+        SourcePolicy {
+            required: self.required_by_default,
+            ..Default::default()
+        }
+    }
+}
+
+/// Source names use underscores, but dashes are accepted as well since they are common on the
+/// command line.
+pub fn normalize_name(name: &str) -> String {
+    name.replace('-', "_")
+}
@@ -1,38 +1,57 @@
-use std::{env::temp_dir, fs::File, io::Read};
+use std::{error::Error, fs::File, io::Read};
 
 use flate2::read::GzDecoder;
-use git2::FetchOptions;
+use futures::future::LocalBoxFuture;
 use serde::Deserialize;
+use serde_json::json;
 use tracing::info;
 
 use crate::{
     records::types::{Certainty, IngestibleData, ModIdentifier, Notice, NoticeRecord, Source},
-    sources::{Diagnostics, RecordSource},
+    sources::{
+        DiagnosticEntry, Diagnostics, RecordSource,
+        cache::{self, CommitInfo, Snapshot},
+        clone_shallow,
+        registry::{SourceDescriptor, SourceOption, SourceOptions},
+    },
 };
 
 pub const REPOSITORY_URL: &str = "https://github.com/emipa606/UseThisInstead";
+const REPLACEMENTS_FILE_GZ: &str = "replacements.json.gz";
+/// Name of the decompressed replacements file in snapshots
+const REPLACEMENTS_FILE: &str = "replacements.json";
 
 #[derive(Deserialize)]
 pub struct UtiData {
-    pub rules: Vec<UtiReplacement>,
+    /// Rules are validated one by one, see `UtiReplacement::validate`
+    pub rules: Vec<serde_json::Value>,
     pub version: String,
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// A replacement rule. Missing and malformed fields are `None` or 0.
 pub struct UtiReplacement {
-    #[serde(default, deserialize_with = "are_you_kidding_me::opt_string")]
     pub old_package_id: Option<String>,
-    #[serde(deserialize_with = "are_you_kidding_me::u64")]
     pub old_workshop_id: u64,
-    #[serde(default, deserialize_with = "are_you_kidding_me::opt_string")]
     pub new_package_id: Option<String>,
-    #[serde(deserialize_with = "are_you_kidding_me::string")]
-    pub new_name: String,
-    #[serde(deserialize_with = "are_you_kidding_me::u64")]
+    pub new_name: Option<String>,
     pub new_workshop_id: u64,
 }
 
+impl UtiReplacement {
+    /// Read the rule at index `idx` of the rules list, along with the coercions that were necessary.
+    pub fn validate(idx: usize, value: &serde_json::Value) -> (Self, Vec<DiagnosticEntry>) {
+        let mut rule = are_you_kidding_me::Rule::new(idx, value);
+        let replacement = Self {
+            old_package_id: rule.string("oldPackageId"),
+            old_workshop_id: rule.u64("oldWorkshopId"),
+            new_package_id: rule.string("newPackageId"),
+            new_name: rule.string("newName"),
+            new_workshop_id: rule.u64("newWorkshopId"),
+        };
+        (replacement, rule.entries)
+    }
+}
+
 // I shit you not, the UTI replacements json is at least partially hand-written
 // json and completely unvalidated.
 //
@@ -40,94 +59,166 @@ pub struct UtiReplacement {
 // There are records where the package id is null or an integer (someone pasted the workshop id of the mod there).
 // There are also records where the workshop id is an actual integer instead of a string as with
 // most records.
+//
+// Every coercion is recorded, so the broken rules can be reported upstream.
 mod are_you_kidding_me {
-    use serde::{Deserialize, Deserializer};
-
-    #[derive(Deserialize)]
-    #[serde(untagged)]
-    enum U64OrString {
-        Number(u64),
-        String(String),
-        OptString(Option<String>),
-    }
-
-    pub fn u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        if let Ok(deserialized) = U64OrString::deserialize(deserializer) {
-            match deserialized {
-                U64OrString::Number(n) => Ok(n),
-                U64OrString::String(s) => s.parse().map_err(serde::de::Error::custom),
-                U64OrString::OptString(os) => os.unwrap_or("0".into()).parse().map_err(serde::de::Error::custom),
+    use serde_json::Value;
+
+    use crate::sources::DiagnosticEntry;
+
+    /// A rule being read, collecting the coercions applied to its fields.
+    pub struct Rule<'a> {
+        idx: usize,
+        value: &'a Value,
+        pub entries: Vec<DiagnosticEntry>,
+    }
+
+    impl<'a> Rule<'a> {
+        pub fn new(idx: usize, value: &'a Value) -> Self {
+            let mut rule = Self {
+                idx,
+                value,
+                entries: Vec::new(),
+            };
+            if !value.is_object() {
+                rule.problem(None, "wrong_type", "Rule is not an object", Some(value));
             }
-        } else {
-            Ok(0)
+            rule
         }
-    }
 
-    pub fn string<'de, D>(deserializer: D) -> Result<String, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        if let Ok(deserialized) = U64OrString::deserialize(deserializer) {
-            match deserialized {
-                U64OrString::Number(n) => Ok(n.to_string()),
-                U64OrString::String(s) => Ok(s),
-                U64OrString::OptString(os) => Ok(os.unwrap_or_default()),
+        /// Look up a field, reporting it if it is missing or null. Fields of rules that are not
+        /// objects are not reported again.
+        fn field(&mut self, field: &str) -> Option<&'a Value> {
+            let value = self.value.as_object()?.get(field);
+            match value {
+                None => self.problem(Some(field), "missing", "Field is missing", None),
+                Some(Value::Null) => self.problem(Some(field), "null", "Value is null", value),
+                Some(_) => {}
             }
-        } else {
-            Ok(String::new())
+            value.filter(|x| !x.is_null())
         }
-    }
 
-    pub fn opt_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        if let Ok(deserialized) = U64OrString::deserialize(deserializer) {
-            match deserialized {
-                U64OrString::Number(n) => Ok(Some(n.to_string())),
-                U64OrString::String(s) => Ok(Some(s)),
-                U64OrString::OptString(os) => Ok(os),
+        pub fn problem(
+            &mut self,
+            field: Option<&str>,
+            kind: &str,
+            message: impl Into<String>,
+            value: Option<&Value>,
+        ) {
+            let mut location = format!("rules[{}]", self.idx);
+            if let Some(field) = field {
+                location.push('.');
+                location.push_str(field);
+            }
+            self.entries.push(DiagnosticEntry {
+                kind: kind.into(),
+                location,
+                message: message.into(),
+                value: value.cloned(),
+            });
+        }
+
+        /// Workshop ids are usually strings, sometimes numbers. Anything unusable becomes 0.
+        pub fn u64(&mut self, field: &str) -> u64 {
+            let Some(value) = self.field(field) else {
+                return 0;
+            };
+            let parsed = match value {
+                Value::Number(n) => n.as_u64(),
+                Value::String(s) => s.trim().parse().ok(),
+                _ => None,
+            };
+
+            match parsed {
+                Some(0) => {
+                    let message = "Workshop id is 0";
+                    self.problem(Some(field), "zero_workshop_id", message, Some(value));
+                    0
+                }
+                Some(n) => n,
+                None => {
+                    let message = "Workshop id is not a number";
+                    self.problem(Some(field), "invalid_workshop_id", message, Some(value));
+                    0
+                }
+            }
+        }
+
+        /// Numbers are converted, anything else unusable becomes `None`.
+        pub fn string(&mut self, field: &str) -> Option<String> {
+            let value = self.field(field)?;
+            match value {
+                Value::String(s) if !s.trim().is_empty() => Some(s.clone()),
+                Value::String(_) => {
+                    self.problem(Some(field), "empty_string", "Value is empty", Some(value));
+                    None
+                }
+                Value::Number(n) => {
+                    let message = "Expected a string but found a number, possibly a workshop id";
+                    self.problem(Some(field), "number_in_string_field", message, Some(value));
+                    Some(n.to_string())
+                }
+                _ => {
+                    self.problem(Some(field), "wrong_type", "Expected a string", Some(value));
+                    None
+                }
             }
-        } else {
-            Ok(None)
         }
     }
 }
 
 pub struct UseThisInstead {
+    repository: String,
     records: Vec<IngestibleData>,
     diagnostics: Diagnostics,
 }
 
+impl Default for UseThisInstead {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl UseThisInstead {
+    pub const DESCRIPTOR: SourceDescriptor = SourceDescriptor {
+        name: "use_this_instead",
+        description: "Replacement suggestions from the Use This Instead mod",
+        options: &[SourceOption {
+            name: "repository",
+            description: "Git repository containing replacements.json.gz",
+            default: Some(REPOSITORY_URL),
+        }],
+        enabled_by_default: true,
+        required_by_default: false,
+        create: |_, options| Ok(Box::new(Self::from_options(options)?)),
+    };
+
     pub fn new() -> Self {
+        Self::with_repository(REPOSITORY_URL.into())
+    }
+
+    pub fn with_repository(repository: String) -> Self {
         Self {
+            repository,
             diagnostics: Diagnostics::new(),
             records: Vec::new(),
         }
     }
-}
-
-impl RecordSource for UseThisInstead {
-    async fn fetch(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let mut repo_fo = FetchOptions::new();
-        repo_fo.depth(1);
 
-        let mut repo_builder = git2::build::RepoBuilder::new();
-        repo_builder.fetch_options(repo_fo);
-
-        let mut repo_dir = temp_dir();
-        repo_dir.push("jumli_uti");
+    pub fn from_options(options: &SourceOptions) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::with_repository(
+            options
+                .string("repository")?
+                .unwrap_or_else(|| REPOSITORY_URL.into()),
+        ))
+    }
 
-        info!("Cloning UTI Repo to {repo_dir:?}.");
-        let repo = repo_builder.clone(REPOSITORY_URL, &repo_dir)?;
-        self.diagnostics.add_git_info(&repo);
+    async fn snapshot(&self) -> Result<Snapshot, Box<dyn Error>> {
+        info!("Cloning UTI Repo {}.", self.repository);
+        let (repo_dir, repo) = clone_shallow(self.repository.clone()).await?;
         info!("Cloned UTI Repo.");
 
-        let replacements_file_gz = repo_dir.join("replacements.json.gz");
+        let replacements_file_gz = repo_dir.path().join(REPLACEMENTS_FILE_GZ);
         let mut gz_decoder = GzDecoder::new(
             File::open(&replacements_file_gz)
                 .map_err(|e| format!("Failed to open {replacements_file_gz:#?}: {e}"))?,
@@ -137,6 +228,25 @@ impl RecordSource for UseThisInstead {
             .read_to_string(&mut replacements_file_plain)
             .map_err(|e| format!("Failed to decode {replacements_file_gz:#?}: {e}"))?;
 
+        let mut snapshot = Snapshot::new(CommitInfo::from_head(&repo).ok());
+        snapshot
+            .files
+            .insert(REPLACEMENTS_FILE.into(), replacements_file_plain);
+        Ok(snapshot)
+    }
+}
+
+impl RecordSource for UseThisInstead {
+    fn fetch_snapshot(&mut self) -> LocalBoxFuture<'_, Result<Snapshot, Box<dyn Error>>> {
+        Box::pin(self.snapshot())
+    }
+
+    fn process(&mut self, mut snapshot: Snapshot) -> Result<(), Box<dyn std::error::Error>> {
+        let replacements_file_plain = snapshot
+            .files
+            .remove(REPLACEMENTS_FILE)
+            .ok_or_else(|| format!("Snapshot does not contain {REPLACEMENTS_FILE}"))?;
+
         let uti_data: UtiData = serde_json::from_str(
             replacements_file_plain
                 .strip_prefix("\u{feff}")
@@ -144,22 +254,66 @@ impl RecordSource for UseThisInstead {
         )
         .map_err(|e| format!("Failed to deserialize replacements file: {e}"))?;
 
-        for replacement in uti_data.rules {
+        let (mut coerced_rules, mut discarded_rules) = (0, 0);
+        for (idx, rule) in uti_data.rules.iter().enumerate() {
+            let (replacement, mut entries) = UtiReplacement::validate(idx, rule);
+
             let mut identifiers = vec![ModIdentifier::WorkshopId(replacement.old_workshop_id)];
             // Some of our lovely modders do not think unique package names are important
             // Also, some records are missing old/new package ids since the maintainer switched to
             // handwritten json...
-            if let Some(old_package_id) = replacement.old_package_id && let Some(new_package_id) = replacement.new_package_id
-                   && old_package_id != new_package_id {
+            if let Some(old_package_id) = replacement.old_package_id
+                && let Some(new_package_id) = &replacement.new_package_id
+                && &old_package_id != new_package_id
+            {
                 identifiers.push(ModIdentifier::PackageId(old_package_id));
             }
+            // finalize ignores invalid identifiers, such rules could never be matched
+            identifiers.retain(|x| !x.is_invalid());
+
+            let mut name = replacement.new_name.or(replacement.new_package_id);
+            // The alternative can still be linked to, it only lacks a name to show
+            if name.is_none() && replacement.new_workshop_id != 0 {
+                entries.push(DiagnosticEntry {
+                    kind: "unnamed_alternative".into(),
+                    location: format!("rules[{idx}]"),
+                    message: "Alternative has no name, it is named after its workshop id".into(),
+                    value: Some(json!(replacement.new_workshop_id)),
+                });
+                name = Some(format!("Workshop item {}", replacement.new_workshop_id));
+            }
+            let coerced = !entries.is_empty();
+
+            let discard_reason = match (&name, identifiers.is_empty()) {
+                (_, true) => Some("Rule does not identify the replaced mod"),
+                (None, false) => Some("Rule does not identify the alternative"),
+                (Some(_), false) => None,
+            };
+            if let Some(reason) = discard_reason {
+                entries.push(DiagnosticEntry {
+                    kind: "discarded_rule".into(),
+                    location: format!("rules[{idx}]"),
+                    message: reason.into(),
+                    value: Some(rule.clone()),
+                });
+            }
+            for entry in entries {
+                self.diagnostics.add_entry(entry);
+            }
+            let Some(name) = name.filter(|_| discard_reason.is_none()) else {
+                discarded_rules += 1;
+                continue;
+            };
+            if coerced {
+                coerced_rules += 1;
+            }
 
             self.records.push(IngestibleData {
                 identifiers,
                 notices: vec![NoticeRecord {
                     notice: Notice::UseAlternative(
-                        replacement.new_name,
-                        Some(replacement.new_workshop_id),
+                        name,
+                        Some(replacement.new_workshop_id).filter(|x| *x != 0),
                         None,
                     ),
                     date: None,
@@ -167,10 +321,15 @@ impl RecordSource for UseThisInstead {
                     source: Source::UseThisInsteadDatabase,
                     context_url: None,
                     historical: false,
+                    alternative_chain: None,
+                    corroborations: Vec::new(),
                 }],
             });
         }
 
+        if discarded_rules > 0 {
+            info!("Discarded {discarded_rules} unusable UTI rules.");
+        }
         info!(
             "Completed UTI processing, yielding {} records.",
             self.records.len(),
@@ -179,13 +338,20 @@ impl RecordSource for UseThisInstead {
             .add_property("raw_records_count", self.records.len().to_string());
         self.diagnostics
             .add_property("uti_version", uti_data.version);
+        self.diagnostics
+            .add_property("rules_count", uti_data.rules.len().to_string());
+        self.diagnostics
+            .add_property("coerced_rules_count", coerced_rules.to_string());
+        self.diagnostics
+            .add_property("discarded_rules_count", discarded_rules.to_string());
 
-        info!("Deleting UTI Repo {repo_dir:?}.",);
-
-        std::fs::remove_dir_all(repo_dir)?;
         Ok(())
     }
 
+    fn cache_key(&self) -> Option<String> {
+        Some(cache::remote_key("use_this_instead", &self.repository))
+    }
+
     fn get_records(&mut self) -> Option<&mut Vec<IngestibleData>> {
         if self.records.is_empty() {
             None
@@ -194,11 +360,76 @@ impl RecordSource for UseThisInstead {
         }
     }
 
-    fn get_diagnostics(self) -> Diagnostics {
-        self.diagnostics
+    fn take_diagnostics(&mut self) -> Diagnostics {
+        std::mem::take(&mut self.diagnostics)
     }
 
     fn get_name(&self) -> &'static str {
         "Use This Instead"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(rules: serde_json::Value) -> UseThisInstead {
+        let mut snapshot = Snapshot::new(None);
+        snapshot.files.insert(
+            REPLACEMENTS_FILE.into(),
+            json!({"version": "1", "rules": rules}).to_string(),
+        );
+        let mut source = UseThisInstead::new();
+        source.process(snapshot).unwrap();
+        source
+    }
+
+    fn property(source: &UseThisInstead, key: &str) -> Option<String> {
+        source
+            .diagnostics
+            .get_properties()?
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.clone())
+    }
+
+    #[test]
+    fn keeps_alternatives_known_by_workshop_id() {
+        let source = process(json!([
+            {"oldWorkshopId": "1000000001", "newName": "Named", "newWorkshopId": "2000000001"},
+            {"oldWorkshopId": "1000000002", "newName": null, "newPackageId": "", "newWorkshopId": 2000000002_u64},
+            {"oldWorkshopId": "1000000003", "newName": null, "newWorkshopId": "0"},
+            {"oldWorkshopId": "0", "newName": "Unmatchable", "newWorkshopId": "2000000004"},
+        ]));
+
+        assert_eq!(source.records.len(), 2);
+        assert!(matches!(
+            &source.records[1].notices[0].notice,
+            Notice::UseAlternative(name, Some(2000000002), None) if name == "Workshop item 2000000002"
+        ));
+        assert_eq!(
+            property(&source, "coerced_rules_count").as_deref(),
+            Some("2")
+        );
+        assert_eq!(
+            property(&source, "discarded_rules_count").as_deref(),
+            Some("2")
+        );
+
+        let discarded: Vec<_> = source
+            .diagnostics
+            .get_entries()
+            .unwrap()
+            .iter()
+            .filter(|x| x.kind == "discarded_rule")
+            .map(|x| (x.location.as_str(), x.message.as_str()))
+            .collect();
+        assert_eq!(
+            discarded,
+            [
+                ("rules[2]", "Rule does not identify the alternative"),
+                ("rules[3]", "Rule does not identify the replaced mod"),
+            ]
+        );
+    }
+}
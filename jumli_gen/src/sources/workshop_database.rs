@@ -0,0 +1,379 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    path::PathBuf,
+};
+
+use futures::future::LocalBoxFuture;
+use serde::Deserialize;
+use tracing::info;
+
+use crate::{
+    records::types::{Certainty, IngestibleData, ModIdentifier, Notice, NoticeRecord, Source},
+    sources::{
+        Diagnostics, RecordSource,
+        cache::Snapshot,
+        registry::{SourceDescriptor, SourceOption},
+    },
+};
+
+/// Name of the dump in snapshots
+const DUMP_FILE: &str = "workshop_database.json";
+
+/// RimSort's community Steam database, keyed by workshop id.
+#[derive(Deserialize)]
+struct RimSortDatabase {
+    database: BTreeMap<String, RimSortEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RimSortEntry {
+    package_id: Option<String>,
+    #[serde(default)]
+    game_versions: Vec<String>,
+}
+
+/// Response of the Steam Web API's `GetPublishedFileDetails`, e.g. as exported by SteamCMD tooling.
+#[derive(Deserialize)]
+struct WebApiExport {
+    response: WebApiResponse,
+}
+
+#[derive(Deserialize)]
+struct WebApiResponse {
+    #[serde(default)]
+    publishedfiledetails: Vec<WebApiFileDetails>,
+}
+
+#[derive(Deserialize)]
+struct WebApiFileDetails {
+    publishedfileid: String,
+    /// 1 if the item exists, other EResult codes otherwise
+    result: i64,
+    #[serde(default)]
+    tags: Vec<WebApiTag>,
+}
+
+#[derive(Deserialize)]
+struct WebApiTag {
+    tag: String,
+}
+
+/// A mod of the dump, independent of its format.
+pub struct WorkshopMod {
+    pub workshop_id: String,
+    pub package_id: Option<String>,
+    /// Supported RimWorld releases, empty if unknown
+    pub versions: Vec<String>,
+}
+
+pub struct WorkshopDatabase {
+    path: PathBuf,
+    latest_release: String,
+    records: Vec<IngestibleData>,
+    diagnostics: Diagnostics,
+}
+
+impl WorkshopDatabase {
+    pub const DESCRIPTOR: SourceDescriptor = SourceDescriptor {
+        name: "workshop_database",
+        description: "Out of date mods, from a local dump of Steam Workshop metadata (RimSort's Steam database or a GetPublishedFileDetails response)",
+        options: &[SourceOption {
+            name: "path",
+            description: "JSON file containing the dump",
+            default: None,
+        }],
+        enabled_by_default: false,
+        required_by_default: false,
+        create: |config, options| {
+            let path = options
+                .path("path")?
+                .ok_or("The path option is required (sources.workshop_database.path).")?;
+            Ok(Box::new(Self::new(
+                path,
+                config.latest_rimworld_release.clone(),
+            )))
+        },
+    };
+
+    /// Read the dump at `path`, reporting mods that do not support `latest_release`.
+    pub fn new(path: PathBuf, latest_release: String) -> Self {
+        Self {
+            path,
+            latest_release,
+            records: Vec::new(),
+            diagnostics: Diagnostics::new(),
+        }
+    }
+
+    async fn snapshot(&self) -> Result<Snapshot, Box<dyn Error>> {
+        info!("Reading Steam Workshop metadata from {:?}.", self.path);
+        let contents = std::fs::read_to_string(&self.path)
+            .map_err(|e| format!("Unable to read workshop metadata {:?}: {e}", self.path))?;
+
+        let mut snapshot = Snapshot::new(None);
+        snapshot.files.insert(DUMP_FILE.into(), contents);
+        Ok(snapshot)
+    }
+
+    /// Mods of a dump in either supported format, along with the name of the format.
+    pub fn parse(contents: &str) -> Result<(&'static str, Vec<WorkshopMod>), Box<dyn Error>> {
+        let value: serde_json::Value = serde_json::from_str(contents)
+            .map_err(|e| format!("Unable to parse workshop metadata: {e}"))?;
+
+        if value.get("database").is_some() {
+            let dump: RimSortDatabase = serde_json::from_value(value)
+                .map_err(|e| format!("Unable to parse RimSort Steam database: {e}"))?;
+            let mods = dump
+                .database
+                .into_iter()
+                .map(|(workshop_id, entry)| WorkshopMod {
+                    workshop_id,
+                    package_id: entry.package_id,
+                    versions: entry.game_versions,
+                })
+                .collect();
+            Ok(("RimSort Steam database", mods))
+        } else if value.get("response").is_some() {
+            let dump: WebApiExport = serde_json::from_value(value)
+                .map_err(|e| format!("Unable to parse GetPublishedFileDetails response: {e}"))?;
+            let mods = dump
+                .response
+                .publishedfiledetails
+                .into_iter()
+                // Deleted, hidden or otherwise unavailable items carry no metadata
+                .filter(|x| x.result == 1)
+                .map(|details| WorkshopMod {
+                    workshop_id: details.publishedfileid,
+                    package_id: None,
+                    // Supported releases are tagged like any other category
+                    versions: details
+                        .tags
+                        .into_iter()
+                        .map(|x| x.tag)
+                        .filter(|x| is_release(x))
+                        .collect(),
+                })
+                .collect();
+            Ok(("GetPublishedFileDetails response", mods))
+        } else {
+            Err("Unrecognized workshop metadata, expected RimSort's Steam database or a GetPublishedFileDetails response.".into())
+        }
+    }
+}
+
+/// Whether a tag names a RimWorld release such as `1.5`.
+fn is_release(tag: &str) -> bool {
+    let mut parts = tag.trim().split('.');
+    parts.next().is_some_and(|x| x.parse::<u32>().is_ok())
+        && parts.next().is_some_and(|x| x.parse::<u32>().is_ok())
+        && parts.next().is_none()
+}
+
+impl RecordSource for WorkshopDatabase {
+    fn fetch_snapshot(&mut self) -> LocalBoxFuture<'_, Result<Snapshot, Box<dyn Error>>> {
+        Box::pin(self.snapshot())
+    }
+
+    fn process(&mut self, mut snapshot: Snapshot) -> Result<(), Box<dyn Error>> {
+        let contents = snapshot
+            .files
+            .remove(DUMP_FILE)
+            .ok_or_else(|| format!("Snapshot does not contain {DUMP_FILE}"))?;
+        let (format, mods) = Self::parse(&contents)?;
+
+        // An original mod and its continuation often share a package id, which would merge their
+        // records, so package ids are only used if a single item has them
+        let mut package_id_counts: HashMap<String, usize> = HashMap::new();
+        for package_id in mods.iter().filter_map(|x| x.package_id.as_deref()) {
+            *package_id_counts
+                .entry(package_id.trim().to_lowercase())
+                .or_default() += 1;
+        }
+        let shared_package_ids = package_id_counts.values().filter(|x| **x > 1).count();
+
+        let (mut unknown_versions, mut invalid_ids) = (0, 0);
+        let mod_count = mods.len();
+        for workshop_mod in mods {
+            if workshop_mod.versions.is_empty() {
+                unknown_versions += 1;
+                continue;
+            }
+            if workshop_mod
+                .versions
+                .iter()
+                .any(|x| x.trim() == self.latest_release)
+            {
+                continue;
+            }
+
+            let Ok(workshop_id) = workshop_mod.workshop_id.trim().parse::<u64>() else {
+                invalid_ids += 1;
+                continue;
+            };
+            let mut identifiers = vec![ModIdentifier::WorkshopId(workshop_id)];
+            if let Some(package_id) = workshop_mod.package_id
+                && package_id_counts.get(&package_id.trim().to_lowercase()) == Some(&1)
+            {
+                identifiers.push(ModIdentifier::PackageId(package_id));
+            }
+
+            self.records.push(IngestibleData {
+                identifiers,
+                notices: vec![NoticeRecord {
+                    notice: Notice::OutOfDate,
+                    date: None,
+                    // The mod's tags are authoritative, even if they may simply be outdated
+                    certainty: Certainty::High,
+                    source: Source::WorkshopDatabase,
+                    context_url: Some(format!(
+                        "https://steamcommunity.com/sharedfiles/filedetails/?id={workshop_id}"
+                    )),
+                    historical: false,
+                    alternative_chain: None,
+                    corroborations: Vec::new(),
+                }],
+            });
+        }
+
+        if invalid_ids > 0 {
+            self.diagnostics.log_error(format!(
+                "Skipped {invalid_ids} mods with invalid workshop ids."
+            ));
+        }
+
+        info!(
+            "Completed Workshop database processing, yielding {} records.",
+            self.records.len()
+        );
+        self.diagnostics.add_property("format", format);
+        self.diagnostics
+            .add_property("latest_release", self.latest_release.clone());
+        self.diagnostics
+            .add_property("mods_count", mod_count.to_string());
+        self.diagnostics
+            .add_property("unknown_versions_count", unknown_versions.to_string());
+        self.diagnostics
+            .add_property("shared_package_ids_count", shared_package_ids.to_string());
+        self.diagnostics
+            .add_property("raw_records_count", self.records.len().to_string());
+
+        Ok(())
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        // The dump is a local file, there is nothing to fall back to
+        None
+    }
+
+    fn get_records(&mut self) -> Option<&mut Vec<IngestibleData>> {
+        if self.records.is_empty() {
+            None
+        } else {
+            Some(&mut self.records)
+        }
+    }
+
+    fn take_diagnostics(&mut self) -> Diagnostics {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    fn get_name(&self) -> &'static str {
+        "Steam Workshop Database"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(contents: &str) -> WorkshopDatabase {
+        let mut snapshot = Snapshot::new(None);
+        snapshot.files.insert(DUMP_FILE.into(), contents.into());
+        let mut source = WorkshopDatabase::new(PathBuf::new(), "1.6".into());
+        source.process(snapshot).unwrap();
+        source
+    }
+
+    fn property(source: &WorkshopDatabase, key: &str) -> Option<String> {
+        source
+            .diagnostics
+            .get_properties()?
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.clone())
+    }
+
+    #[test]
+    fn parses_rimsort_database() {
+        let contents = include_str!("../../fixtures/workshop_database/rimsort_steam_db.json");
+        let (format, mods) = WorkshopDatabase::parse(contents).unwrap();
+        assert_eq!(format, "RimSort Steam database");
+        assert_eq!(mods.len(), 4);
+
+        let source = process(contents);
+        // Harmony supports 1.6, 1000000002 has no versions and the last id is invalid
+        assert_eq!(source.records.len(), 1);
+        assert_eq!(
+            source.records[0].identifiers,
+            [
+                ModIdentifier::WorkshopId(1000000001),
+                ModIdentifier::PackageId("example.outdated".into())
+            ]
+        );
+        assert!(matches!(
+            source.records[0].notices[0].notice,
+            Notice::OutOfDate
+        ));
+        assert_eq!(
+            property(&source, "unknown_versions_count").as_deref(),
+            Some("1")
+        );
+        assert!(source.diagnostics.has_errors());
+    }
+
+    #[test]
+    fn parses_web_api_response() {
+        let contents =
+            include_str!("../../fixtures/workshop_database/get_published_file_details.json");
+        let (format, mods) = WorkshopDatabase::parse(contents).unwrap();
+        assert_eq!(format, "GetPublishedFileDetails response");
+        // Unavailable items are skipped
+        assert_eq!(mods.len(), 2);
+        assert_eq!(mods[0].versions, ["1.5", "1.6"]);
+
+        let source = process(contents);
+        assert_eq!(source.records.len(), 1);
+        assert_eq!(
+            source.records[0].identifiers,
+            [ModIdentifier::WorkshopId(1000000001)]
+        );
+    }
+
+    #[test]
+    fn shared_package_ids_are_not_used() {
+        // An original mod and its up-to-date continuation
+        let source = process(
+            r#"{"database": {
+                "1000000001": {"packageId": "author.mod", "gameVersions": ["1.4"]},
+                "2000000002": {"packageId": "Author.Mod", "gameVersions": ["1.6"]}
+            }}"#,
+        );
+        assert_eq!(source.records.len(), 1);
+        assert_eq!(
+            source.records[0].identifiers,
+            [ModIdentifier::WorkshopId(1000000001)]
+        );
+        assert_eq!(
+            property(&source, "shared_package_ids_count").as_deref(),
+            Some("1")
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_dumps() {
+        assert!(WorkshopDatabase::parse("{}").is_err());
+        assert!(WorkshopDatabase::parse("not json").is_err());
+    }
+}
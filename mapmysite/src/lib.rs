@@ -93,6 +93,12 @@ impl SitemapUrl {
     }
 }
 
+impl Default for Sitemap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Sitemap {
     /// Creates a new empty `Sitemap`.
     pub fn new() -> Self {